@@ -0,0 +1,194 @@
+use std::fmt::{self, Display};
+
+use crate::tween::Tween;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Ability {
+    DoubleJump,
+    Dash,
+}
+
+impl Ability {
+    pub const ALL: [Self; 2] = [Self::DoubleJump, Self::Dash];
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::DoubleJump => "double-jump",
+            Self::Dash => "dash",
+        }
+    }
+
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|ability| ability.name() == name)
+    }
+
+    const fn max_charges(self) -> u8 {
+        match self {
+            Self::DoubleJump | Self::Dash => 1,
+        }
+    }
+
+    // In seconds.
+    const fn cooldown(self) -> f64 {
+        match self {
+            Self::DoubleJump => 0.2,
+            Self::Dash => 0.6,
+        }
+    }
+
+    const fn waveform(self) -> &'static [f32] {
+        match self {
+            Self::DoubleJump => &DOUBLE_JUMP_WAVEFORM,
+            Self::Dash => &DASH_WAVEFORM,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AbilityState {
+    pub ability: Ability,
+    pub charges: u8,
+    pub cooldown: f64,
+    pub tween: Tween,
+}
+
+impl AbilityState {
+    pub fn new(ability: Ability) -> Self {
+        Self {
+            ability,
+            charges: ability.max_charges(),
+            cooldown: 0.0,
+            tween: Tween::new(ability.waveform()),
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.charges > 0 && self.cooldown <= 0.0
+    }
+
+    pub const fn is_active(&self) -> bool {
+        !self.tween.stopped
+    }
+
+    pub fn trigger(&mut self) -> bool {
+        if !self.is_ready() {
+            return false;
+        }
+        self.charges -= 1;
+        self.cooldown = self.ability.cooldown();
+        self.tween.reset();
+        self.tween.stopped = false;
+        true
+    }
+
+    pub fn update(&mut self, delta: f64) {
+        self.cooldown = (self.cooldown - delta).max(0.0);
+        self.tween.update(delta);
+    }
+
+    pub const fn land(&mut self) {
+        self.charges = self.ability.max_charges();
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Abilities {
+    unlocked: Vec<AbilityState>,
+}
+
+impl Abilities {
+    pub fn new(unlocked: &[Ability]) -> Self {
+        let mut abilities = Self::default();
+        for ability in unlocked {
+            abilities.grant(*ability);
+        }
+        abilities
+    }
+
+    // Returns false if the ability was already unlocked.
+    pub fn grant(&mut self, ability: Ability) -> bool {
+        if self.has(ability) {
+            return false;
+        }
+        self.unlocked.push(AbilityState::new(ability));
+        true
+    }
+
+    pub fn has(&self, ability: Ability) -> bool {
+        self.get(ability).is_some()
+    }
+
+    pub fn get(&self, ability: Ability) -> Option<&AbilityState> {
+        self.unlocked.iter().find(|state| state.ability == ability)
+    }
+
+    pub fn get_mut(&mut self, ability: Ability) -> Option<&mut AbilityState> {
+        self.unlocked
+            .iter_mut()
+            .find(|state| state.ability == ability)
+    }
+
+    pub fn trigger(&mut self, ability: Ability) -> bool {
+        self.get_mut(ability).is_some_and(AbilityState::trigger)
+    }
+
+    pub fn is_active(&self, ability: Ability) -> bool {
+        self.get(ability).is_some_and(AbilityState::is_active)
+    }
+
+    // Current value of the ability motion curve, None when it is not running.
+    pub fn value(&mut self, ability: Ability) -> Option<f32> {
+        let state = self.get_mut(ability)?;
+        if !state.is_active() {
+            return None;
+        }
+        let value = state.tween.value();
+        state.is_active().then_some(value)
+    }
+
+    pub fn update(&mut self, delta: f64) {
+        for state in &mut self.unlocked {
+            state.update(delta);
+        }
+    }
+
+    pub fn land(&mut self) {
+        for state in &mut self.unlocked {
+            state.land();
+        }
+    }
+
+    // Parses the inside of `Abilities { double-jump, dash }`.
+    pub fn parse_list(rest: &str) -> Vec<Ability> {
+        rest.split(|c: char| c == ',' || c.is_ascii_whitespace())
+            .filter(|name| !name.is_empty() && name != &"{" && name != &"}")
+            .filter_map(|name| {
+                let ability = Ability::from_name(name);
+                if ability.is_none() {
+                    eprintln!("ERROR: Unknown ability {name}");
+                }
+                ability
+            })
+            .collect()
+    }
+}
+
+impl Display for Abilities {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<&str> = self
+            .unlocked
+            .iter()
+            .map(|state| state.ability.name())
+            .collect();
+        write!(f, "Abilities {{ {} }}", names.join(", "))
+    }
+}
+
+const DOUBLE_JUMP_WAVEFORM: [f32; 24] = [
+    0.0, 20.0, 38.0, 52.0, 63.0, 72.0, 79.0, 84.0, 88.0, 91.0, 93.0, 95.0, 96.0, 97.0, 98.0, 99.0,
+    100.0, 100.0, 99.0, 98.0, 96.0, 94.0, 91.0, 88.0,
+];
+
+const DASH_WAVEFORM: [f32; 8] = [100.0, 100.0, 100.0, 95.0, 85.0, 65.0, 40.0, 10.0];
@@ -0,0 +1,259 @@
+use macroquad::math::Rect;
+use macroquad::texture::Image;
+
+pub const PAGE_SIZE: u16 = 2048;
+// Transparent gap between sprites, so linear filtering does not bleed neighbours in.
+pub const PADDING: u16 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub page: usize,
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Placement {
+    pub fn rect(&self) -> Rect {
+        Rect::new(
+            f32::from(self.x),
+            f32::from(self.y),
+            f32::from(self.width),
+            f32::from(self.height),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Page {
+    width: u16,
+    height: u16,
+    shelf_x: u16,
+    shelf_y: u16,
+    shelf_height: u16,
+}
+
+// Shelf packing: sprites go tallest first, left to right in rows, a new
+// page is started once a row does not fit anymore. Sprites bigger than a
+// page get a page of their own. Returns the placements in input order and
+// the size of every page.
+pub fn pack(
+    sizes: &[(u16, u16)],
+    page_size: u16,
+    padding: u16,
+) -> (Vec<Placement>, Vec<(u16, u16)>) {
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|i| std::cmp::Reverse(sizes.get(*i).map_or(0, |size| size.1)));
+
+    let mut placements = vec![
+        Placement {
+            page: 0,
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+        };
+        sizes.len()
+    ];
+    let mut pages: Vec<Page> = Vec::new();
+    let mut current: Option<usize> = None;
+
+    for i in order {
+        let (width, height) = sizes.get(i).copied().unwrap_or_default();
+        let (padded_width, padded_height) = (width + padding, height + padding);
+
+        if padded_width > page_size || padded_height > page_size {
+            pages.push(Page {
+                width,
+                height,
+                ..Page::default()
+            });
+            if let Some(placement) = placements.get_mut(i) {
+                *placement = Placement {
+                    page: pages.len() - 1,
+                    x: 0,
+                    y: 0,
+                    width,
+                    height,
+                };
+            }
+            continue;
+        }
+
+        let fits = |page: &Page| {
+            if page.shelf_x + padded_width <= page_size {
+                page.shelf_y + padded_height.max(page.shelf_height) <= page_size
+            } else {
+                page.shelf_y + page.shelf_height + padded_height <= page_size
+            }
+        };
+        let page_index = match current {
+            Some(index) if pages.get(index).is_some_and(fits) => index,
+            _ => {
+                pages.push(Page::default());
+                current = Some(pages.len() - 1);
+                pages.len() - 1
+            }
+        };
+        let page = pages
+            .get_mut(page_index)
+            .expect("page index should come from the pages we just looked at");
+        if page.shelf_x + padded_width > page_size {
+            page.shelf_y += page.shelf_height;
+            page.shelf_x = 0;
+            page.shelf_height = 0;
+        }
+        if let Some(placement) = placements.get_mut(i) {
+            *placement = Placement {
+                page: page_index,
+                x: page.shelf_x,
+                y: page.shelf_y,
+                width,
+                height,
+            };
+        }
+        page.shelf_x += padded_width;
+        page.shelf_height = page.shelf_height.max(padded_height);
+        page.width = page.width.max(page.shelf_x);
+        page.height = page.height.max(page.shelf_y + page.shelf_height);
+    }
+
+    let page_sizes = pages.iter().map(|page| (page.width, page.height)).collect();
+    (placements, page_sizes)
+}
+
+pub struct Atlas {
+    pub pages: Vec<Image>,
+    pub placements: Vec<Placement>,
+}
+
+impl Atlas {
+    pub fn build(images: &[Image], page_size: u16, padding: u16) -> Self {
+        let sizes: Vec<(u16, u16)> = images
+            .iter()
+            .map(|image| (image.width, image.height))
+            .collect();
+        let (placements, page_sizes) = pack(&sizes, page_size, padding);
+        let mut pages: Vec<Image> = page_sizes
+            .iter()
+            .map(|(width, height)| Image {
+                bytes: vec![0; usize::from(*width) * usize::from(*height) * 4],
+                width: *width,
+                height: *height,
+            })
+            .collect();
+        for (image, placement) in images.iter().zip(&placements) {
+            if let Some(page) = pages.get_mut(placement.page) {
+                blit(page, image, placement);
+            }
+        }
+        Self { pages, placements }
+    }
+}
+
+fn blit(page: &mut Image, image: &Image, placement: &Placement) {
+    let row_length = usize::from(image.width) * 4;
+    let page_row_length = usize::from(page.width) * 4;
+    if row_length == 0 {
+        return;
+    }
+    for (row, source) in image.bytes.chunks_exact(row_length).enumerate() {
+        let start =
+            (usize::from(placement.y) + row) * page_row_length + usize::from(placement.x) * 4;
+        if let Some(target) = page.bytes.get_mut(start..start + row_length) {
+            target.copy_from_slice(source);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::color::{Color, BLUE, RED};
+
+    use super::*;
+
+    const fn overlaps(a: &Placement, b: &Placement) -> bool {
+        a.page == b.page
+            && a.x < b.x + b.width
+            && b.x < a.x + a.width
+            && a.y < b.y + b.height
+            && b.y < a.y + a.height
+    }
+
+    fn assert_valid(placements: &[Placement], page_sizes: &[(u16, u16)]) {
+        for (i, placement) in placements.iter().enumerate() {
+            let (width, height) = page_sizes.get(placement.page).copied().unwrap_or_default();
+            assert!(placement.x + placement.width <= width);
+            assert!(placement.y + placement.height <= height);
+            for other in placements.iter().skip(i + 1) {
+                assert!(!overlaps(placement, other), "{placement:?} {other:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn packs_without_overlap() {
+        let sizes = [(200, 350), (607, 192), (141, 138), (280, 280), (236, 96)];
+        let (placements, page_sizes) = pack(&sizes, 1024, PADDING);
+        assert_eq!(page_sizes.len(), 1);
+        for (placement, size) in placements.iter().zip(sizes) {
+            assert_eq!((placement.width, placement.height), size);
+        }
+        assert_valid(&placements, &page_sizes);
+    }
+
+    #[test]
+    fn overflows_into_new_pages() {
+        let sizes = [(300, 300); 10];
+        let (placements, page_sizes) = pack(&sizes, 640, 0);
+        assert_eq!(page_sizes.len(), 3);
+        assert_valid(&placements, &page_sizes);
+    }
+
+    #[test]
+    fn oversized_sprite_gets_own_page() {
+        let sizes = [(100, 100), (3000, 50), (100, 100)];
+        let (placements, page_sizes) = pack(&sizes, 1024, PADDING);
+        assert_eq!(page_sizes.len(), 2);
+        let [small, big, other] = placements.as_slice() else {
+            panic!("expected three placements, got {placements:?}");
+        };
+        assert_eq!(page_sizes.get(big.page), Some(&(3000, 50)));
+        assert_eq!(small.page, other.page);
+        assert_valid(&placements, &page_sizes);
+    }
+
+    #[test]
+    fn empty_input_has_no_pages() {
+        let (placements, page_sizes) = pack(&[], PAGE_SIZE, PADDING);
+        assert!(placements.is_empty());
+        assert!(page_sizes.is_empty());
+    }
+
+    #[test]
+    fn build_copies_pixels() {
+        let images = [
+            Image::gen_image_color(4, 8, RED),
+            Image::gen_image_color(6, 3, BLUE),
+        ];
+        let atlas = Atlas::build(&images, 64, 1);
+        let [page] = atlas.pages.as_slice() else {
+            panic!("expected one page, got {}", atlas.pages.len());
+        };
+        for (image, placement) in images.iter().zip(&atlas.placements) {
+            let expected = image.get_pixel(0, 0);
+            for x in 0..u32::from(placement.width) {
+                for y in 0..u32::from(placement.height) {
+                    let pixel =
+                        page.get_pixel(u32::from(placement.x) + x, u32::from(placement.y) + y);
+                    assert_eq!(pixel, expected);
+                }
+            }
+        }
+        // Padding stays transparent.
+        let red = atlas.placements.first().expect("a placement per image");
+        let gap = page.get_pixel(u32::from(red.x + red.width), u32::from(red.y));
+        assert_eq!(gap, Color::new(0.0, 0.0, 0.0, 0.0));
+    }
+}
@@ -1,53 +1,630 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
 
-use macroquad::audio::Sound;
+use macroquad::audio::{
+    load_sound_from_bytes, play_sound, set_sound_volume, stop_sound, PlaySoundParams, Sound,
+};
+use macroquad::file::load_file;
+use macroquad::math::{Rect, Vec2};
+use macroquad::rand;
 use macroquad::ui;
 
-use crate::SOUND_PATHS;
+use crate::camera::Camera;
+use crate::diagnostics::Diagnostics;
+use crate::emitter::Emitter;
+use crate::manifest::AssetEntry;
+use crate::music::Music;
+use crate::overlay::{DebugPanel, Panels};
 
 pub struct Audio {
-    samples: HashMap<&'static str, Sample>,
+    samples: HashMap<String, Sample>,
+    cues: HashMap<String, Cue>,
+    // Paths of the cue files, cues from all of them end up in `cues`.
+    cue_sources: Vec<(String, String)>,
+    pub mixer: Mixer,
+    music: Music,
+    // Sample ids currently looping for emitters.
+    ambient: HashSet<String>,
+    pub diagnostics: Diagnostics,
 }
 
 impl Audio {
-    pub async fn new() -> Self {
-        let paths = SOUND_PATHS.split('\n');
-        let mut samples = HashMap::with_capacity(5);
-        for path in paths {
-            let name = path
-                .split('/')
-                .last()
-                .expect("Path should be in a samples/ folder, thus have a '/'")
-                .split('.')
-                .take(1)
-                .last()
-                .expect("Path should include .ogg extension");
-            let sample = Sample::new(path).await;
-            samples.insert(name, sample);
-        }
-        Self { samples }
+    pub async fn from_loaded(
+        loaded: Vec<(AssetEntry, Result<Sample, String>)>,
+        cues: Vec<(AssetEntry, Result<String, String>)>,
+        diagnostics: Diagnostics,
+    ) -> Result<Self, String> {
+        let mut samples = HashMap::with_capacity(loaded.len());
+        for (entry, sample) in loaded {
+            let sample = match sample {
+                Ok(sample) => sample,
+                Err(why) => {
+                    diagnostics.missing("sound", &entry.id, &why);
+                    Sample::silent(&entry.path).await
+                }
+            };
+            samples.insert(entry.id, sample);
+        }
+        let mut cue_sources = Vec::with_capacity(cues.len());
+        for (entry, contents) in cues {
+            let contents = contents.unwrap_or_else(|why| {
+                diagnostics.missing("cues", &entry.id, &why);
+                String::new()
+            });
+            cue_sources.push((entry.path, contents));
+        }
+        let mut audio = Self {
+            samples,
+            cues: HashMap::new(),
+            cue_sources,
+            mixer: Mixer::default(),
+            music: Music::default(),
+            ambient: HashSet::new(),
+            diagnostics,
+        };
+        audio.build_cues();
+        audio.diagnostics.check()?;
+        Ok(audio)
+    }
+
+    fn build_cues(&mut self) {
+        self.cues.clear();
+        for (path, contents) in &self.cue_sources {
+            match load_cues(contents) {
+                Ok(cues) => self.cues.extend(cues),
+                Err(why) => eprintln!("ERROR: {path}: {why}"),
+            }
+        }
+    }
+
+    pub fn cue_paths(&self) -> impl Iterator<Item = &str> {
+        self.cue_sources.iter().map(|(path, _)| path.as_str())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload_cues(&mut self, path: &str, contents: String) -> Result<(), String> {
+        load_cues(&contents).map_err(|why| format!("{path}: {why}"))?;
+        let source = self
+            .cue_sources
+            .iter_mut()
+            .find(|(source, _)| source == path)
+            .ok_or_else(|| format!("{path} is not a known cue file"))?;
+        source.1 = contents;
+        self.build_cues();
+        Ok(())
+    }
+
+    pub fn sound_ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self.samples.keys().map(String::as_str).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    // Plays a cue as if it came from `pos` in the world, nothing far off screen.
+    pub fn play_at(&self, cue: &str, pos: Vec2, camera: &Camera) {
+        if let Some(spatial) = Spatial::new(pos, camera.viewport_rect()) {
+            self.play_with_volume(cue, spatial.volume);
+        }
+    }
+
+    pub fn play(&self, cue: &str) {
+        self.play_with_volume(cue, 1.0);
+    }
+
+    // Picks one of the cue variants at random, `volume` scales the cue volume.
+    pub fn play_with_volume(&self, cue: &str, volume: f32) {
+        let Some(cue_info) = self.cues.get(cue) else {
+            self.diagnostics.missing("cue", cue, "not in any cue file");
+            return;
+        };
+        let Some(variant) = cue_info.pick() else {
+            return;
+        };
+        let Some(sample) = self.samples.get(variant) else {
+            self.diagnostics
+                .missing("sound", variant, &format!("used by cue {cue}"));
+            return;
+        };
+        play_sound(
+            sample.sound,
+            PlaySoundParams {
+                looped: false,
+                volume: cue_info.volume * volume * self.mixer.gain(cue_info.bus),
+            },
+        );
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.samples.values().map(|sample| sample.path.as_str())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn reload(&mut self, path: &str, bytes: &[u8]) -> Result<(), String> {
+        check_ogg(path, bytes)?;
+        let sample = self
+            .samples
+            .values_mut()
+            .find(|sample| sample.path == path)
+            .ok_or_else(|| format!("{path} is not a known sample"))?;
+        *sample = Sample::from_bytes(path, bytes)
+            .await
+            .map_err(|why| format!("couldn't load {path}: {why}"))?;
+        Ok(())
+    }
+
+    // Crossfades to the looping sample `id`, None fades the music out.
+    pub fn play_music(&mut self, id: Option<&str>) {
+        let Some(id) = id else {
+            self.music.stop();
+            return;
+        };
+        match self.samples.get(id) {
+            Some(sample) => self.music.play(id, sample.music),
+            None => self.diagnostics.missing("sound", id, "used as music"),
+        }
+    }
+
+    pub fn update(&mut self, delta: f64, ducked: bool) {
+        self.music.set_ducked(ducked);
+        self.music.update(delta, self.mixer.gain(Bus::Music));
+    }
+
+    // Loops the sounds of the emitters that can be heard from `listener`.
+    // Emitters sharing a sound play it once, as loud as the closest one.
+    pub fn update_emitters(&mut self, emitters: &[Emitter], listener: Vec2) {
+        let mut volumes: HashMap<&str, f32> = HashMap::new();
+        for emitter in emitters {
+            let volume = emitter.attenuation(listener);
+            if volume > 0.0 {
+                let entry = volumes.entry(emitter.sound.as_str()).or_default();
+                *entry = entry.max(volume);
+            }
+        }
+        let samples = &self.samples;
+        self.ambient.retain(|id| {
+            let audible = volumes.contains_key(id.as_str());
+            if !audible {
+                if let Some(sample) = samples.get(id) {
+                    stop_sound(sample.ambient);
+                }
+            }
+            audible
+        });
+        let gain = self.mixer.gain(Bus::Sfx);
+        for (id, volume) in volumes {
+            let Some(sample) = self.samples.get(id) else {
+                self.diagnostics.missing("sound", id, "used by an emitter");
+                continue;
+            };
+            if self.ambient.insert(id.to_owned()) {
+                play_sound(
+                    sample.ambient,
+                    PlaySoundParams {
+                        looped: true,
+                        volume: volume * gain,
+                    },
+                );
+            } else {
+                set_sound_volume(sample.ambient, volume * gain);
+            }
+        }
+    }
+
+    pub fn register_panels<'a>(&'a self, panels: &mut Panels<'a>) {
+        panels.register("audio", self);
     }
 
     pub fn debug(&self) {
+        ui::root_ui().label(
+            None,
+            &format!("music: {}", self.music.current().unwrap_or("none")),
+        );
+        for id in self.music.fading() {
+            ui::root_ui().label(None, &format!("fading out: {id}"));
+        }
         for (name, sample) in &self.samples {
-            if ui::root_ui().button(None, *name) {
-                macroquad::audio::play_sound_once(sample.sound);
+            if ui::root_ui().button(None, name.as_str()) {
+                let volume = self.mixer.gain(Bus::Sfx);
+                play_sound(
+                    sample.sound,
+                    PlaySoundParams {
+                        looped: false,
+                        volume,
+                    },
+                );
+            }
+        }
+        let mut names: Vec<&String> = self.cues.keys().collect();
+        names.sort();
+        for name in names {
+            if ui::root_ui().button(None, format!("cue {name}")) {
+                self.play(name);
             }
         }
+        self.diagnostics.ui();
+    }
+}
+
+impl DebugPanel for Audio {
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("music: {}", self.music.current().unwrap_or("none"))];
+        lines.extend(self.music.fading().map(|id| format!("fading out: {id}")));
+        lines.extend(self.mixer.to_string().lines().map(str::to_owned));
+        lines
     }
 }
 
 #[derive(Debug, Clone)]
-struct Sample {
-    path: &'static str,
+// Cues, music and emitters each play the sample on their own handle, since
+// stopping a handle or changing its volume changes everything playing on it.
+pub struct Sample {
+    path: String,
     sound: Sound,
+    music: Sound,
+    ambient: Sound,
 }
 
 impl Sample {
-    pub async fn new(path: &'static str) -> Self {
-        let sound = macroquad::audio::load_sound(path)
+    pub async fn new(path: &str) -> Result<Self, String> {
+        let bytes = load_file(path).await.map_err(|why| why.to_string())?;
+        check_ogg(path, &bytes)?;
+        Self::from_bytes(path, &bytes).await
+    }
+
+    // Stands in for a sample that failed to load.
+    pub async fn silent(path: &str) -> Self {
+        Self::from_bytes(path, &silent_wav())
             .await
-            .expect("Sound file loading error");
-        Self { path, sound }
+            .expect("Generated silent wav should always load")
+    }
+
+    async fn from_bytes(path: &str, bytes: &[u8]) -> Result<Self, String> {
+        let load = || async {
+            load_sound_from_bytes(bytes)
+                .await
+                .map_err(|why| why.to_string())
+        };
+        Ok(Self {
+            path: path.to_owned(),
+            sound: load().await?,
+            music: load().await?,
+            ambient: load().await?,
+        })
+    }
+}
+
+// How a sound at some world position is heard from the camera. Full volume
+// on screen, fading out over half a screen past the edge and culled beyond.
+// Only the volume changes, the macroquad audio backend can't pan.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spatial {
+    pub volume: f32,
+}
+
+impl Spatial {
+    pub fn new(pos: Vec2, viewport: Rect) -> Option<Self> {
+        let half_size = Vec2::new(viewport.w, viewport.h) * 0.5;
+        let center = Vec2::new(viewport.x, viewport.y) + half_size;
+        let offset = pos - center;
+        let outside = (offset.abs() - half_size).max(Vec2::ZERO).length();
+        let range = half_size.max_element();
+        if range <= 0.0 || outside >= range {
+            return None;
+        }
+        Some(Self {
+            volume: 1.0 - outside / range,
+        })
+    }
+}
+
+// Named sound effect that gameplay code plays, see `load_cues` for the format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    // Sample ids, one is picked at random every time the cue plays.
+    pub variants: Vec<String>,
+    pub volume: f32,
+    pub bus: Bus,
+}
+
+impl Cue {
+    fn pick(&self) -> Option<&str> {
+        if self.variants.is_empty() {
+            return None;
+        }
+        let index = rand::gen_range(0, self.variants.len());
+        self.variants.get(index).map(String::as_str)
+    }
+}
+
+// One cue per line: Cue <name> <sample>[,<sample>..] [volume:v] [bus:b]
+// Blank lines and lines starting with `#` are skipped.
+fn load_cues(contents: &str) -> Result<HashMap<String, Cue>, String> {
+    let mut cues = HashMap::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let at_line = |why: String| format!("line {}: {}", number + 1, why);
+        let mut tokens = line.split_ascii_whitespace();
+        if tokens.next() != Some("Cue") {
+            return Err(at_line(format!("Expected a Cue, got {line}")));
+        }
+        let name = tokens
+            .next()
+            .ok_or_else(|| at_line("Cue without a name".to_owned()))?;
+        let variants: Vec<String> = tokens
+            .next()
+            .ok_or_else(|| at_line(format!("Cue {name} without samples")))?
+            .split(',')
+            .filter(|variant| !variant.is_empty())
+            .map(str::to_owned)
+            .collect();
+        let mut cue = Cue {
+            variants,
+            volume: 1.0,
+            bus: Bus::Sfx,
+        };
+        for token in tokens {
+            let (key, value) = token
+                .split_once(':')
+                .ok_or_else(|| at_line(format!("Expected key:value, got {token}")))?;
+            let number = || {
+                value
+                    .parse::<f32>()
+                    .map_err(|_| at_line(format!("Expected a number, got {value}")))
+            };
+            match key {
+                "volume" => cue.volume = number()?,
+                "bus" => {
+                    cue.bus = Bus::from_name(value)
+                        .ok_or_else(|| at_line(format!("Unknown bus {value}")))?;
+                }
+                _ => return Err(at_line(format!("Unknown cue field {key}"))),
+            }
+        }
+        cues.insert(name.to_owned(), cue);
+    }
+    Ok(cues)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bus {
+    Master,
+    Music,
+    Sfx,
+    Ui,
+}
+
+impl Bus {
+    pub const ALL: [Self; 4] = [Self::Master, Self::Music, Self::Sfx, Self::Ui];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Master => "master",
+            Self::Music => "music",
+            Self::Sfx => "sfx",
+            Self::Ui => "ui",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|bus| bus.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BusSettings {
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl Default for BusSettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            muted: false,
+        }
+    }
+}
+
+// Every sound plays on a bus, its volume is scaled by that bus and by master.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mixer {
+    master: BusSettings,
+    music: BusSettings,
+    sfx: BusSettings,
+    ui: BusSettings,
+}
+
+impl Mixer {
+    pub const fn bus(&self, bus: Bus) -> BusSettings {
+        match bus {
+            Bus::Master => self.master,
+            Bus::Music => self.music,
+            Bus::Sfx => self.sfx,
+            Bus::Ui => self.ui,
+        }
+    }
+
+    const fn bus_mut(&mut self, bus: Bus) -> &mut BusSettings {
+        match bus {
+            Bus::Master => &mut self.master,
+            Bus::Music => &mut self.music,
+            Bus::Sfx => &mut self.sfx,
+            Bus::Ui => &mut self.ui,
+        }
+    }
+
+    pub const fn set_volume(&mut self, bus: Bus, volume: f32) {
+        self.bus_mut(bus).volume = volume.clamp(0.0, 1.0);
+    }
+
+    pub const fn toggle_mute(&mut self, bus: Bus) {
+        let settings = self.bus_mut(bus);
+        settings.muted = !settings.muted;
+    }
+
+    pub fn gain(&self, bus: Bus) -> f32 {
+        let gain = |settings: BusSettings| {
+            if settings.muted {
+                0.0
+            } else {
+                settings.volume
+            }
+        };
+        let master = gain(self.bus(Bus::Master));
+        if bus == Bus::Master {
+            master
+        } else {
+            master * gain(self.bus(bus))
+        }
+    }
+
+    // Parses the rest of a `Bus <name> <volume> [muted]` settings line.
+    pub fn parse_bus(&mut self, rest: &str) -> Result<(), String> {
+        let mut tokens = rest.split_ascii_whitespace();
+        let bus = tokens.next().and_then(Bus::from_name);
+        let volume = tokens.next().and_then(|volume| volume.parse().ok());
+        let (Some(bus), Some(volume)) = (bus, volume) else {
+            return Err(format!("couldn't parse Bus {rest}"));
+        };
+        self.set_volume(bus, volume);
+        self.bus_mut(bus).muted = tokens.next() == Some("muted");
+        Ok(())
+    }
+}
+
+// Bus <name> <volume> [muted]
+impl Display for Mixer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for bus in Bus::ALL {
+            let settings = self.bus(bus);
+            write!(f, "Bus {} {}", bus.name(), settings.volume)?;
+            if settings.muted {
+                write!(f, " muted")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// The decoder panics on garbage, so the ogg pages are checked first: every
+// page is complete and matches its checksum, and the last one ends the
+// stream. The audio inside the pages isn't decoded.
+fn check_ogg(path: &str, bytes: &[u8]) -> Result<(), String> {
+    validate_ogg(bytes).map_err(|why| format!("{path} is not a valid ogg file: {why}"))
+}
+
+fn validate_ogg(bytes: &[u8]) -> Result<(), &'static str> {
+    let mut rest = bytes;
+    loop {
+        let (header, after) = rest.split_first_chunk::<27>().ok_or("truncated page")?;
+        let [b'O', b'g', b'g', b'S', version, flags, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, c0, c1, c2, c3, segments] =
+            *header
+        else {
+            return Err("missing the page signature");
+        };
+        if version != 0 {
+            return Err("unknown page version");
+        }
+        let (table, after) = after
+            .split_at_checked(usize::from(segments))
+            .ok_or("truncated page")?;
+        let length = table.iter().copied().map(usize::from).sum();
+        let (body, after) = after.split_at_checked(length).ok_or("truncated page")?;
+        let (before_crc, _) = header.split_at(22);
+        let crc = [before_crc, &[0; 4], &[segments], table, body]
+            .iter()
+            .fold(0, |crc, part| ogg_crc(crc, part));
+        if crc != u32::from_le_bytes([c0, c1, c2, c3]) {
+            return Err("page checksum mismatch");
+        }
+        if after.is_empty() {
+            // The last page flags the end of the stream.
+            return if flags & 0x04 == 0 {
+                Err("truncated stream")
+            } else {
+                Ok(())
+            };
+        }
+        rest = after;
+    }
+}
+
+// Ogg's CRC-32, unreflected with no final xor, continuing from `crc`.
+fn ogg_crc(crc: u32, bytes: &[u8]) -> u32 {
+    bytes.iter().fold(crc, |crc, byte| {
+        (0..8).fold(crc ^ (u32::from(*byte) << 24), |crc, _| {
+            if crc & 0x8000_0000 == 0 {
+                crc << 1
+            } else {
+                (crc << 1) ^ 0x04c1_1db7
+            }
+        })
+    })
+}
+
+// A tenth of a second of 16 bit mono silence.
+fn silent_wav() -> Vec<u8> {
+    let sample_rate: u32 = 44100;
+    let samples: u16 = 4410;
+    let data_length = u32::from(samples) * 2;
+    let mut wav = Vec::with_capacity(44 + usize::from(samples) * 2);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_length).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_length.to_le_bytes());
+    wav.resize(wav.len() + usize::from(samples) * 2, 0);
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SELECT: &[u8] = include_bytes!("../assets/samples/unfa-ui-select.ogg");
+
+    #[test]
+    fn parses_cues() {
+        let cues = load_cues("# comment\nCue land a,b volume:0.5 bus:ui\n").expect("valid cues");
+        let land = cues.get("land").expect("land is parsed");
+        assert_eq!(land.variants, ["a", "b"]);
+        assert_eq!((land.volume, land.bus), (0.5, Bus::Ui));
+        // Playback can't change pitch, so cues don't take one.
+        assert_eq!(
+            load_cues("Cue jump a pitch:0.1").err().as_deref(),
+            Some("line 1: Unknown cue field pitch")
+        );
+    }
+
+    #[test]
+    fn accepts_a_real_ogg() {
+        assert_eq!(validate_ogg(SELECT), Ok(()));
+    }
+
+    #[test]
+    fn rejects_broken_oggs() {
+        assert_eq!(validate_ogg(b"RIFF"), Err("truncated page"));
+        assert_eq!(
+            validate_ogg(&silent_wav()),
+            Err("missing the page signature")
+        );
+        let truncated = SELECT.get(..SELECT.len() - 100).unwrap_or_default();
+        assert_eq!(validate_ogg(truncated), Err("truncated page"));
+        let mut corrupt = SELECT.to_vec();
+        if let Some(byte) = corrupt.get_mut(100) {
+            *byte ^= 0xff;
+        }
+        assert_eq!(validate_ogg(&corrupt), Err("page checksum mismatch"));
     }
 }
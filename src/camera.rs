@@ -1,58 +1,101 @@
 use std::default::Default;
 
-use macroquad::input::{is_key_down, mouse_position, KeyCode};
+use macroquad::camera::Camera2D;
+use macroquad::color_u8;
+use macroquad::input::mouse_position;
 use macroquad::math::{vec2, Rect, Vec2};
+use macroquad::prelude::Color;
+use macroquad::shapes::{draw_rectangle, draw_rectangle_lines};
 use macroquad::window::{screen_height, screen_width};
 
-use crate::common::map;
+use crate::common::{map, narrow};
+use crate::input::{Action, Input};
+use crate::overlay::{DebugPanel, Panels};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Camera {
     pub target: Vec2,
     pub rotation: f32,
     pub zoom: Vec2,
-    pub followed_pos: Option<Vec2>,
-    pub followed_rot: Option<f32>,
+    pub follow: Option<Follow>,
+    // Before the last follow tick, drawing interpolates from them.
+    previous_target: Vec2,
+    previous_zoom: Vec2,
 }
 
 impl Camera {
     #[must_use]
     pub fn new() -> Self {
         let starting_zoom = 1.0 / screen_width();
+        let zoom = vec2(
+            starting_zoom,
+            starting_zoom * screen_width() / screen_height(),
+        );
         Self {
             target: vec2(0.0, 0.0),
             rotation: 0.0,
-            zoom: vec2(
-                starting_zoom,
-                starting_zoom * screen_width() / screen_height(),
-            ),
-            followed_pos: None,
-            followed_rot: None,
+            zoom,
+            follow: None,
+            previous_target: vec2(0.0, 0.0),
+            previous_zoom: zoom,
         }
     }
 
-    pub fn update(&mut self) {
-        if let Some(target) = self.followed_pos {
-            self.target = target;
-        }
-        if let Some(rot) = self.followed_rot {
-            self.rotation = rot;
-        }
+    pub const fn unfollow(&mut self) {
+        self.follow = None;
     }
 
-    pub fn unfollow(&mut self) {
-        self.followed_pos = None;
-        self.followed_rot = None;
+    // Zones zoom relative to the zoom the camera had when following started.
+    pub fn set_follow(&mut self, follow: Option<Follow>) {
+        let base_zoom = self.follow.map_or(self.zoom, |old| old.base_zoom);
+        self.follow = follow.map(|follow| Follow {
+            base_zoom,
+            ..follow
+        });
     }
 
-    pub fn set_follow(&mut self, position: Option<Vec2>, _rotation: Option<f32>) {
-        self.followed_pos = position;
-        //self.followed_rot = rotation;
+    // Moves towards the followed subject, does nothing when not following.
+    pub fn update_follow(
+        &mut self,
+        subject: Vec2,
+        facing_right: bool,
+        on_ground: bool,
+        framing: Framing,
+        delta: f64,
+    ) {
+        self.previous_target = self.target;
+        self.previous_zoom = self.zoom;
+        if let Some(follow) = &mut self.follow {
+            let followed = follow.update(subject, facing_right, on_ground, delta);
+            let mut goal = framing.target.unwrap_or(followed);
+            let zoom_goal = follow.base_zoom * framing.zoom.unwrap_or(1.0);
+            let amount = smoothing(follow.smoothing, delta);
+            self.zoom += (zoom_goal - self.zoom) * amount;
+            if let Some(bounds) = framing.bounds {
+                let (width, height) = viewport_size(zoom_goal);
+                goal = clamp_to_bounds(goal, vec2(width, height), bounds);
+            }
+            self.target += (goal - self.target) * amount;
+        }
+    }
+
+    // Target and zoom to draw with, `alpha` of the way between the last two
+    // follow ticks. A camera moved by hand is drawn where it is.
+    #[must_use]
+    pub fn interpolated(&self, alpha: f32) -> (Vec2, Vec2) {
+        if self.follow.is_some() {
+            (
+                self.previous_target.lerp(self.target, alpha),
+                self.previous_zoom.lerp(self.zoom, alpha),
+            )
+        } else {
+            (self.target, self.zoom)
+        }
     }
 
     #[must_use]
     pub fn viewport_size(&self) -> (f32, f32) {
-        (2.0 * (1.0 / self.zoom.x), 2.0 * (1.0 / self.zoom.y))
+        viewport_size(self.zoom)
     }
 
     #[must_use]
@@ -99,61 +142,227 @@ impl Camera {
         let mouse = vec2(mouse.0, mouse.1);
         self.screen_to_world(mouse)
     }
+
+    pub fn register_panels<'a>(&'a self, panels: &mut Panels<'a>) {
+        panels.register("camera", self);
+        panels.register("mouse", MousePanel(self));
+    }
 }
 
-impl Default for Camera {
-    fn default() -> Self {
-        Self::new()
+// The view, the follow dead zone and the world origin.
+impl DebugPanel for Camera {
+    fn lines(&self) -> Vec<String> {
+        vec![format!(
+            "target: {}, zoom: {:?}, view_port: {:?}",
+            self.target,
+            self.zoom,
+            self.viewport_size(),
+        )]
+    }
+
+    fn draw_world(&self, _camera: &Camera2D) {
+        let Rect { x, y, w, h } = self.viewport_rect();
+        draw_rectangle_lines(x, y, w, h, w / 100.0, color_u8!(50, 120, 100, 100));
+        if let Some(follow) = &self.follow {
+            let Rect { x, y, w, h } = follow.dead_zone_rect();
+            draw_rectangle_lines(x, y, w, h, 4.0, color_u8!(120, 120, 200, 100));
+        }
+        let (width, height) = (screen_width(), screen_height());
+        draw_rectangle_lines(
+            self.target.x - width,
+            self.target.y - height,
+            width * 2.0,
+            height * 2.0,
+            50.0,
+            color_u8!(50, 120, 100, 100),
+        );
+        draw_rectangle(-5.0, -5.0, 10.0, 10.0, color_u8!(180, 180, 180, 255));
     }
 }
 
-#[allow(clippy::many_single_char_names)]
-pub fn top_down_camera_controls(camera: &mut Camera) {
-    let w = is_key_down(KeyCode::W) || is_key_down(KeyCode::Comma);
-    let s = is_key_down(KeyCode::S) || is_key_down(KeyCode::O);
-    let a = is_key_down(KeyCode::A);
+struct MousePanel<'a>(&'a Camera);
 
-    #[cfg(not(target_arch = "wasm32"))]
-    let d = is_key_down(KeyCode::E);
-    #[cfg(not(target_arch = "wasm32"))]
-    let e = is_key_down(KeyCode::Period);
+impl DebugPanel for MousePanel<'_> {
+    fn lines(&self) -> Vec<String> {
+        vec![format!(
+            "mouse: {:?}, mouse_world: {}",
+            mouse_position(),
+            self.0.mouse_world_position()
+        )]
+    }
+}
 
-    #[cfg(target_arch = "wasm32")]
-    let d = is_key_down(KeyCode::D);
-    #[cfg(target_arch = "wasm32")]
-    let e = is_key_down(KeyCode::E);
+// Keeps a subject in a dead zone rectangle, looks ahead in the direction it
+// faces and only changes the vertical framing once it lands.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Follow {
+    // Half size of the dead zone, the subject moves inside it freely.
+    pub dead_zone: Vec2,
+    // How far to look in the facing direction.
+    pub look_ahead: f32,
+    // Per second, higher values catch up faster.
+    pub smoothing: f32,
+    pub look_ahead_smoothing: f32,
+    // Zoom when the following started, zones zoom relative to it.
+    base_zoom: Vec2,
+    // Center of the dead zone.
+    focus: Vec2,
+    look: f32,
+}
 
-    let q = is_key_down(KeyCode::Q) || is_key_down(KeyCode::Apostrophe);
-    // scroll
-    if w {
-        // && is_key_pressed(KeyCode::LeftControl) {
-        camera.target.y -= 0.01 / camera.zoom.x;
-        camera.unfollow();
+impl Follow {
+    #[must_use]
+    pub const fn new(subject: Vec2) -> Self {
+        Self {
+            dead_zone: vec2(200.0, 300.0),
+            look_ahead: 300.0,
+            smoothing: 5.0,
+            look_ahead_smoothing: 2.0,
+            base_zoom: vec2(1.0, 1.0),
+            focus: subject,
+            look: 0.0,
+        }
     }
-    if s {
-        // && is_key_pressed(KeyCode::LeftControl) {
-        camera.target.y += 0.01 / camera.zoom.x;
-        camera.unfollow();
+
+    #[must_use]
+    pub fn dead_zone_rect(&self) -> Rect {
+        Rect::new(
+            self.focus.x - self.dead_zone.x,
+            self.focus.y - self.dead_zone.y,
+            self.dead_zone.x * 2.0,
+            self.dead_zone.y * 2.0,
+        )
     }
-    if a {
-        // && is_key_pressed(KeyCode::LeftControl) {
-        camera.target.x -= 0.01 / camera.zoom.x;
-        camera.unfollow();
+
+    // Returns where the camera should end up.
+    fn update(&mut self, subject: Vec2, facing_right: bool, on_ground: bool, delta: f64) -> Vec2 {
+        self.focus.x = self
+            .focus
+            .x
+            .clamp(subject.x - self.dead_zone.x, subject.x + self.dead_zone.x);
+        if on_ground {
+            self.focus.y = subject.y;
+        } else {
+            // Jumps don't move the camera, long falls still do.
+            self.focus.y = self
+                .focus
+                .y
+                .clamp(subject.y - self.dead_zone.y, subject.y + self.dead_zone.y);
+        }
+        let look = if facing_right {
+            self.look_ahead
+        } else {
+            -self.look_ahead
+        };
+        self.look += (look - self.look) * smoothing(self.look_ahead_smoothing, delta);
+        vec2(self.focus.x + self.look, self.focus.y)
     }
-    if d {
-        // && is_key_pressed(KeyCode::LeftControl) {
-        camera.target.x += 0.01 / camera.zoom.x;
-        camera.unfollow();
+}
+
+// Where the camera is kept by the level and by camera zones.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Framing {
+    // The viewport stays inside, or centered on it when it is smaller.
+    pub bounds: Option<Rect>,
+    // Relative to the zoom the following started with.
+    pub zoom: Option<f32>,
+    // Holds the camera still instead of following.
+    pub target: Option<Vec2>,
+}
+
+fn viewport_size(zoom: Vec2) -> (f32, f32) {
+    (2.0 * (1.0 / zoom.x), 2.0 * (1.0 / zoom.y))
+}
+
+fn clamp_to_bounds(target: Vec2, viewport: Vec2, bounds: Rect) -> Vec2 {
+    let clamp = |value: f32, start: f32, length: f32, view: f32| {
+        if view >= length {
+            start + length / 2.0
+        } else {
+            value.clamp(start + view / 2.0, start + length - view / 2.0)
+        }
+    };
+    vec2(
+        clamp(target.x, bounds.x, bounds.w, viewport.x),
+        clamp(target.y, bounds.y, bounds.h, viewport.y),
+    )
+}
+
+// Fraction of the remaining distance to cover this frame, the same per
+// second whatever the frame rate.
+fn smoothing(rate: f32, delta: f64) -> f32 {
+    1.0 - (-rate * narrow(delta)).exp()
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn top_down_camera_controls(camera: &mut Camera, input: &Input) {
+    // scroll
+    let pan = 0.01 / camera.zoom.x;
+    for (action, offset) in [
+        (Action::CameraPanUp, vec2(0.0, -pan)),
+        (Action::CameraPanDown, vec2(0.0, pan)),
+        (Action::CameraPanLeft, vec2(-pan, 0.0)),
+        (Action::CameraPanRight, vec2(pan, 0.0)),
+    ] {
+        if input.down(action) {
+            camera.target += offset;
+            camera.unfollow();
+        }
     }
     // zoom
-    if is_key_down(KeyCode::PageUp) || q {
+    if input.down(Action::CameraZoomOut) {
         camera.zoom.x *= 0.98;
         camera.zoom.y *= 0.98;
         camera.unfollow();
     }
-    if is_key_down(KeyCode::PageDown) || e {
+    if input.down(Action::CameraZoomIn) {
         camera.zoom.x /= 0.98;
         camera.zoom.y /= 0.98;
         camera.unfollow();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stays_inside_the_dead_zone() {
+        let mut follow = Follow::new(vec2(0.0, 0.0));
+        follow.look_ahead = 0.0;
+        for x in [50.0, 150.0, -150.0, 0.0] {
+            assert_eq!(follow.update(vec2(x, 100.0), true, false, 0.1), Vec2::ZERO);
+        }
+        // Past the edge the dead zone is dragged along.
+        assert_eq!(
+            follow.update(vec2(250.0, 0.0), true, false, 0.1),
+            vec2(50.0, 0.0)
+        );
+        assert_eq!(
+            follow.update(vec2(100.0, 0.0), true, false, 0.1),
+            vec2(50.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn looks_ahead_where_the_subject_moves() {
+        let mut follow = Follow::new(vec2(0.0, 0.0));
+        let mut goal = Vec2::ZERO;
+        for _ in 0..100 {
+            goal = follow.update(Vec2::ZERO, true, true, 0.1);
+        }
+        assert!((goal.x - follow.look_ahead).abs() < 0.1);
+        let before = goal.x;
+        goal = follow.update(Vec2::ZERO, false, true, 0.1);
+        assert!(goal.x < before);
+        for _ in 0..100 {
+            goal = follow.update(Vec2::ZERO, false, true, 0.1);
+        }
+        assert!((goal.x + follow.look_ahead).abs() < 0.1);
+    }
+}
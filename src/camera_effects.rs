@@ -0,0 +1,164 @@
+use macroquad::math::{vec2, Vec2};
+
+use crate::common::narrow;
+use crate::tween::Tween;
+
+// At full trauma, in world units and radians.
+const MAX_SHAKE_OFFSET: f32 = 60.0;
+const MAX_SHAKE_ROTATION: f32 = 0.05;
+
+// Screen shake, zoom punches and rotation kicks on top of whatever the camera
+// follows. Every effect plays its envelope once, trauma stacks up to 1.
+#[derive(Debug, Clone)]
+pub struct CameraEffects {
+    trauma: f32,
+    shake: Tween,
+    punch_strength: f32,
+    punch: Tween,
+    kick_strength: f32,
+    kick: Tween,
+    time: f32,
+    // Current envelope values, 0 to 1.
+    shake_amount: f32,
+    punch_amount: f32,
+    kick_amount: f32,
+    // Accessibility setting, 0 turns the effects off.
+    pub scale: f32,
+}
+
+// What to add to the camera this frame.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct CameraOffset {
+    pub target: Vec2,
+    // Multiplies the zoom.
+    pub zoom: f32,
+    pub rotation: f32,
+}
+
+impl CameraEffects {
+    pub fn new() -> Self {
+        Self {
+            trauma: 0.0,
+            shake: Tween::new(&SHAKE_WAVEFORM),
+            punch_strength: 0.0,
+            punch: Tween::new(&PUNCH_WAVEFORM),
+            kick_strength: 0.0,
+            kick: Tween::new(&KICK_WAVEFORM),
+            time: 0.0,
+            shake_amount: 0.0,
+            punch_amount: 0.0,
+            kick_amount: 0.0,
+            scale: 1.0,
+        }
+    }
+
+    fn trauma(&self) -> f32 {
+        self.trauma * self.shake_amount
+    }
+
+    // 0 to 1, the shake grows with the square of the trauma.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma() + amount).min(1.0);
+        self.shake_amount = 1.0;
+        start(&mut self.shake);
+    }
+
+    // Zooms in by `strength`, 0.1 is 10 percent, and back.
+    pub fn zoom_punch(&mut self, strength: f32) {
+        self.punch_strength = strength;
+        start(&mut self.punch);
+    }
+
+    // Tilts by `radians` and swings back.
+    pub fn rotation_kick(&mut self, radians: f32) {
+        self.kick_strength = radians;
+        start(&mut self.kick);
+    }
+
+    pub fn update(&mut self, delta: f64) {
+        self.time += narrow(delta);
+        for (tween, amount) in [
+            (&mut self.shake, &mut self.shake_amount),
+            (&mut self.punch, &mut self.punch_amount),
+            (&mut self.kick, &mut self.kick_amount),
+        ] {
+            tween.update(delta);
+            *amount = tween.value() / 100.0;
+        }
+    }
+
+    pub fn offset(&self) -> CameraOffset {
+        let shake = self.trauma().powi(2) * self.scale;
+        let target = vec2(noise(self.time, 0.0), noise(self.time, 10.0)) * MAX_SHAKE_OFFSET;
+        CameraOffset {
+            target: target * shake,
+            zoom: (self.punch_strength * self.punch_amount).mul_add(self.scale, 1.0),
+            rotation: (self.kick_strength * self.kick_amount).mul_add(
+                self.scale,
+                noise(self.time, 20.0) * MAX_SHAKE_ROTATION * shake,
+            ),
+        }
+    }
+}
+
+fn start(tween: &mut Tween) {
+    tween.reset();
+    tween.stopped = false;
+}
+
+// Smooth noise between -1 and 1, different seeds give unrelated curves.
+fn noise(time: f32, seed: f32) -> f32 {
+    let fast = time.mul_add(31.0, seed).sin();
+    let slow = time.mul_add(17.3, seed * 2.0).sin();
+    slow.mul_add(0.5, fast) / 1.5
+}
+
+const SHAKE_WAVEFORM: [f32; 16] = [
+    100.0, 92.0, 84.0, 76.0, 68.0, 60.0, 52.0, 45.0, 38.0, 31.0, 25.0, 19.0, 13.0, 8.0, 4.0, 0.0,
+];
+
+const PUNCH_WAVEFORM: [f32; 10] = [0.0, 60.0, 100.0, 80.0, 55.0, 35.0, 20.0, 10.0, 4.0, 0.0];
+
+const KICK_WAVEFORM: [f32; 14] = [
+    0.0, 100.0, 80.0, 40.0, 0.0, -30.0, -40.0, -30.0, -15.0, 0.0, 10.0, 8.0, 3.0, 0.0,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settle(effects: &mut CameraEffects) {
+        for _ in 0..60 {
+            effects.update(1.0 / 60.0);
+        }
+    }
+
+    #[test]
+    fn shake_decays() {
+        let mut effects = CameraEffects::new();
+        effects.add_trauma(1.0);
+        let mut last = effects.trauma();
+        for _ in 0..60 {
+            effects.update(1.0 / 60.0);
+            assert!(effects.trauma() <= last);
+            last = effects.trauma();
+        }
+        assert!(last.abs() < f32::EPSILON);
+        assert_eq!(effects.offset().target, Vec2::ZERO);
+    }
+
+    #[test]
+    fn impulses_return_to_rest() {
+        let mut effects = CameraEffects::new();
+        effects.zoom_punch(0.2);
+        effects.rotation_kick(0.1);
+        effects.update(0.05);
+        let offset = effects.offset();
+        assert!(offset.zoom > 1.0);
+        assert!(offset.rotation > 0.0);
+        settle(&mut effects);
+        let offset = effects.offset();
+        assert!((offset.zoom - 1.0).abs() < f32::EPSILON);
+        assert!(offset.rotation.abs() < f32::EPSILON);
+    }
+}
@@ -0,0 +1,118 @@
+use std::fmt::{self, Display};
+
+use macroquad::prelude::*;
+
+use crate::camera::Framing;
+
+// Zoom steps the editor cycles through, relative to the normal zoom.
+const ZOOM_STEPS: [Option<f32>; 4] = [None, Some(0.75), Some(1.5), Some(2.0)];
+
+// An area of the level that changes the camera while the player is inside.
+// It can keep the camera within the area, zoom, or hold the camera still.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CameraZone {
+    pub area: Rect,
+    pub bounds: bool,
+    pub zoom: Option<f32>,
+    pub target: Option<Vec2>,
+}
+
+impl CameraZone {
+    pub const fn new(area: Rect) -> Self {
+        Self {
+            area,
+            bounds: true,
+            zoom: None,
+            target: None,
+        }
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.area.contains(point)
+    }
+
+    // The zone settings on top of the level ones.
+    pub fn framing(&self, level: Framing) -> Framing {
+        Framing {
+            bounds: if self.bounds {
+                Some(self.area)
+            } else {
+                level.bounds
+            },
+            zoom: self.zoom.or(level.zoom),
+            target: self.target.or(level.target),
+        }
+    }
+
+    pub fn cycle_zoom(&mut self) {
+        let current = ZOOM_STEPS
+            .iter()
+            .position(|zoom| *zoom == self.zoom)
+            .unwrap_or(0);
+        self.zoom = ZOOM_STEPS.get(current + 1).copied().unwrap_or_default();
+    }
+
+    pub fn toggle_target(&mut self) {
+        self.target = match self.target {
+            Some(_) => None,
+            None => Some(self.area.point() + self.area.size() * 0.5),
+        };
+    }
+
+    pub fn debug(&self) {
+        let Rect { x, y, w, h } = self.area;
+        let color = color_u8!(120, 200, 240, 255);
+        draw_rectangle_lines(x, y, w, h, 6.0, color);
+        draw_text(&self.to_string(), x + 10.0, y + 40.0, 40.0, color);
+        if let Some(target) = self.target {
+            draw_circle(target.x, target.y, 20.0, color);
+        }
+    }
+
+    // Parses the inside of `CameraZone { x:0, y:0, w:800, h:600, bounds, zoom:1.5, target:400,300 }`.
+    pub fn parse(rest: &str) -> Option<Self> {
+        let mut zone = Self::new(Rect::new(0.0, 0.0, 0.0, 0.0));
+        zone.bounds = false;
+        for pair in rest
+            .split_ascii_whitespace()
+            .filter(|e| e != &"{" && e != &"}")
+        {
+            let pair = pair.trim_matches(',');
+            if pair == "bounds" {
+                zone.bounds = true;
+                continue;
+            }
+            let (key, value) = pair.split_once(':')?;
+            match key {
+                "x" => zone.area.x = value.parse().ok()?,
+                "y" => zone.area.y = value.parse().ok()?,
+                "w" => zone.area.w = value.parse().ok()?,
+                "h" => zone.area.h = value.parse().ok()?,
+                "zoom" => zone.zoom = Some(value.parse().ok()?),
+                "target" => {
+                    let (x, y) = value.split_once(',')?;
+                    zone.target = Some(vec2(x.parse().ok()?, y.parse().ok()?));
+                }
+                _ => return None,
+            }
+        }
+        Some(zone)
+    }
+}
+
+impl Display for CameraZone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Rect { x, y, w, h } = self.area;
+        write!(f, "CameraZone {{ x:{x}, y:{y}, w:{w}, h:{h}")?;
+        if self.bounds {
+            write!(f, ", bounds")?;
+        }
+        if let Some(zoom) = self.zoom {
+            write!(f, ", zoom:{zoom}")?;
+        }
+        if let Some(target) = self.target {
+            write!(f, ", target:{},{}", target.x, target.y)?;
+        }
+        write!(f, " }}")
+    }
+}
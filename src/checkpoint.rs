@@ -0,0 +1,116 @@
+use std::fmt::{self, Display};
+
+use macroquad::prelude::*;
+
+use crate::collider::Collider;
+use crate::sprite::Sprites;
+use crate::tween::Tween;
+
+// An area that becomes the respawn point once the player touches it. The
+// player respawns with the top left of its sprite at the top left of the area,
+// the same way it starts on the spawn marker.
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    pub collider: Collider,
+    pub sprite: Option<String>,
+    pub active: bool,
+    // Raises the sprite when it activates.
+    tween: Tween,
+    lift: f32,
+}
+
+impl Checkpoint {
+    pub fn new(collider: Collider, sprite: Option<String>) -> Self {
+        Self {
+            collider,
+            sprite,
+            active: false,
+            tween: Tween::new(&ACTIVATE_WAVEFORM),
+            lift: 0.0,
+        }
+    }
+
+    pub const fn spawn_pos(&self) -> Vec2 {
+        self.collider.pos
+    }
+
+    pub fn overlaps(&self, rect: Rect) -> bool {
+        self.collider.rect().overlaps(&rect)
+    }
+
+    pub fn activate(&mut self) {
+        self.active = true;
+        self.tween.reset();
+        self.tween.stopped = false;
+    }
+
+    // Active without the animation, for continuing from it.
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+        self.tween.reset();
+        self.lift = 0.0;
+    }
+
+    pub fn update(&mut self, delta: f64) {
+        self.tween.update(delta);
+        self.lift = if self.tween.stopped {
+            0.0
+        } else {
+            self.tween.value()
+        };
+    }
+
+    pub fn draw(&self, sprites: &Sprites) {
+        let Rect { x, y, w, h } = self.collider.rect();
+        let y = y - self.lift;
+        if let Some(sprite) = &self.sprite {
+            sprites.draw(sprite, vec2(x, y));
+        }
+        let color = if self.active {
+            color_u8!(255, 220, 120, 200)
+        } else {
+            color_u8!(150, 150, 150, 120)
+        };
+        draw_rectangle_lines(x, y, w, h, 6.0, color);
+    }
+
+    pub fn debug(&self) {
+        self.collider.draw(Vec2::new(0.0, 0.0));
+    }
+
+    // Parses the inside of `Checkpoint { sprite:star2, x:0, y:0, w:100, h:300 }`.
+    pub fn parse(rest: &str) -> Option<Self> {
+        let mut sprite = None;
+        let mut area = [0f32; 4];
+        for pair in rest
+            .split_ascii_whitespace()
+            .filter(|e| e != &"{" && e != &"}")
+        {
+            let (key, value) = pair.trim_matches(',').split_once(':')?;
+            match key {
+                "sprite" => sprite = Some(value.to_owned()),
+                "x" => area[0] = value.parse().ok()?,
+                "y" => area[1] = value.parse().ok()?,
+                "w" => area[2] = value.parse().ok()?,
+                "h" => area[3] = value.parse().ok()?,
+                _ => return None,
+            }
+        }
+        Some(Self::new(Collider::from(area), sprite))
+    }
+}
+
+impl Display for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Collider { pos, width, height } = &self.collider;
+        write!(f, "Checkpoint {{ ")?;
+        if let Some(sprite) = &self.sprite {
+            write!(f, "sprite:{sprite}, ")?;
+        }
+        write!(f, "x:{}, y:{}, w:{}, h:{} }}", pos.x, pos.y, width, height)
+    }
+}
+
+const ACTIVATE_WAVEFORM: [f32; 12] = [
+    0.0, 30.0, 52.0, 66.0, 72.0, 70.0, 60.0, 44.0, 26.0, 10.0, 2.0, 0.0,
+];
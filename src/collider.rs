@@ -16,7 +16,7 @@ impl Collider {
         Self { pos, width, height }
     }
 
-    pub fn rect(&self) -> Rect {
+    pub const fn rect(&self) -> Rect {
         Rect {
             x: self.pos.x,
             y: self.pos.y,
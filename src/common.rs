@@ -8,9 +8,24 @@ pub fn map(value: f32, start1: f32, stop1: f32, start2: f32, stop2: f32) -> f32
     ((value - start1) / (stop1 - start1)).mul_add(stop2 - start2, start2)
 }
 
+// Time is kept in f64 seconds while drawing and audio take f32, the lost
+// precision doesn't matter for a frame's worth of it.
 #[must_use]
-pub fn norm(value: f32, start: f32, stop: f32) -> f32 {
-    map(value, start, stop, 0.0, 1.0)
+#[allow(clippy::as_conversions, clippy::cast_possible_truncation)]
+pub const fn narrow(value: f64) -> f32 {
+    value as f32
+}
+
+// Drops the fraction, negative values become 0. For turning elapsed time
+// into a step index.
+#[must_use]
+#[allow(
+    clippy::as_conversions,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+pub const fn whole(value: f32) -> usize {
+    value as usize
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
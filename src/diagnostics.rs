@@ -0,0 +1,54 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+use macroquad::ui;
+
+// Asset problems are collected here instead of being printed every frame.
+// In strict mode any problem while loading fails the load, which is what CI
+// wants, see `check`.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    strict: bool,
+    reported: RefCell<HashSet<String>>,
+    messages: RefCell<Vec<String>>,
+}
+
+impl Diagnostics {
+    pub fn new(strict: bool) -> Self {
+        Self {
+            strict,
+            ..Self::default()
+        }
+    }
+
+    // `--strict-assets` on the command line or KAIKA_STRICT_ASSETS in the environment.
+    pub fn strict_from_env() -> bool {
+        std::env::args().any(|arg| arg == "--strict-assets")
+            || std::env::var_os("KAIKA_STRICT_ASSETS").is_some()
+    }
+
+    // Reports every name only once.
+    pub fn missing(&self, kind: &str, name: &str, why: &str) {
+        let key = format!("{kind} {name}");
+        if !self.reported.borrow_mut().insert(key) {
+            return;
+        }
+        let message = format!("Missing {kind} {name}: {why}");
+        eprintln!("ERROR: {message}");
+        self.messages.borrow_mut().push(message);
+    }
+
+    // The first problem reported so far, when in strict mode.
+    pub fn check(&self) -> Result<(), String> {
+        match self.messages.borrow().first() {
+            Some(message) if self.strict => Err(format!("{message} (strict assets mode)")),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn ui(&self) {
+        for message in self.messages.borrow().iter() {
+            ui::root_ui().label(None, message);
+        }
+    }
+}
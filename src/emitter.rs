@@ -0,0 +1,87 @@
+use std::fmt::{self, Display};
+
+use macroquad::prelude::*;
+
+// Radius of the handle that is drawn and clicked in the editor.
+pub const EMITTER_HANDLE: f32 = 30.0;
+
+// A looping ambient sound in the level. Full volume within `radius` of the
+// camera, silent `falloff` further out.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Emitter {
+    pub sound: String,
+    pub pos: Vec2,
+    pub radius: f32,
+    pub falloff: f32,
+    pub volume: f32,
+}
+
+impl Emitter {
+    pub const fn new(sound: String, pos: Vec2) -> Self {
+        Self {
+            sound,
+            pos,
+            radius: 400.0,
+            falloff: 800.0,
+            volume: 1.0,
+        }
+    }
+
+    pub fn attenuation(&self, listener: Vec2) -> f32 {
+        let distance = self.pos.distance(listener) - self.radius;
+        if distance <= 0.0 {
+            self.volume
+        } else if distance >= self.falloff {
+            0.0
+        } else {
+            self.volume * (1.0 - distance / self.falloff)
+        }
+    }
+
+    // Editor gizmo: the handle, the full volume radius and where it fades out.
+    pub fn debug(&self) {
+        let Vec2 { x, y } = self.pos;
+        let color = color_u8!(240, 200, 90, 255);
+        draw_circle(x, y, EMITTER_HANDLE, color_u8!(240, 200, 90, 120));
+        draw_circle_lines(x, y, self.radius, 4.0, color);
+        draw_circle_lines(
+            x,
+            y,
+            self.radius + self.falloff,
+            2.0,
+            color_u8!(240, 200, 90, 100),
+        );
+        draw_text(&self.sound, x + EMITTER_HANDLE, y, 40.0, color);
+    }
+
+    // Parses the inside of `Emitter { sound:hum, x:0, y:0, radius:400, falloff:800, volume:1 }`.
+    pub fn parse(rest: &str) -> Option<Self> {
+        let mut emitter = Self::new(String::new(), Vec2::ZERO);
+        for pair in rest
+            .split_ascii_whitespace()
+            .filter(|e| e != &"{" && e != &"}")
+        {
+            let (key, value) = pair.trim_matches(',').split_once(':')?;
+            match key {
+                "sound" => value.clone_into(&mut emitter.sound),
+                "x" => emitter.pos.x = value.parse().ok()?,
+                "y" => emitter.pos.y = value.parse().ok()?,
+                "radius" => emitter.radius = value.parse().ok()?,
+                "falloff" => emitter.falloff = value.parse().ok()?,
+                "volume" => emitter.volume = value.parse().ok()?,
+                _ => return None,
+            }
+        }
+        (!emitter.sound.is_empty()).then_some(emitter)
+    }
+}
+
+impl Display for Emitter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Emitter {{ sound:{}, x:{}, y:{}, radius:{}, falloff:{}, volume:{} }}",
+            self.sound, self.pos.x, self.pos.y, self.radius, self.falloff, self.volume
+        )
+    }
+}
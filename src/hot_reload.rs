@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use macroquad::prelude::*;
+
+// Seconds between checking the watched files for changes.
+const POLL_INTERVAL: f64 = 0.5;
+// Seconds a log message stays on screen.
+const LOG_DURATION: f64 = 6.0;
+
+// Polls modification times of the asset, level and palette files, so they
+// can be reloaded while the game runs. Native only.
+pub struct HotReload {
+    watched: HashMap<PathBuf, Option<SystemTime>>,
+    last_poll: f64,
+    log: Vec<LogEntry>,
+}
+
+struct LogEntry {
+    time: f64,
+    message: String,
+    is_error: bool,
+}
+
+impl HotReload {
+    pub fn new() -> Self {
+        Self {
+            watched: HashMap::new(),
+            last_poll: 0.0,
+            log: Vec::new(),
+        }
+    }
+
+    pub fn watch<P: AsRef<Path>>(&mut self, path: P) {
+        let path = path.as_ref().to_path_buf();
+        let modified = modified(&path);
+        self.watched.insert(path, modified);
+    }
+
+    // Returns the files that changed since the last poll.
+    pub fn poll(&mut self, time: f64) -> Vec<PathBuf> {
+        if time - self.last_poll < POLL_INTERVAL {
+            return Vec::new();
+        }
+        self.last_poll = time;
+        let mut changed = Vec::new();
+        for (path, last_modified) in &mut self.watched {
+            let modified = modified(path);
+            if modified != *last_modified {
+                *last_modified = modified;
+                changed.push(path.clone());
+            }
+        }
+        changed.sort();
+        changed
+    }
+
+    pub fn report(&mut self, time: f64, result: Result<String, String>) {
+        let (message, is_error) = match result {
+            Ok(message) => {
+                println!("{message}");
+                (message, false)
+            }
+            Err(message) => {
+                eprintln!("ERROR: {message}");
+                (message, true)
+            }
+        };
+        self.log.push(LogEntry {
+            time,
+            message,
+            is_error,
+        });
+    }
+
+    // Draws in screen space, newest message at the bottom.
+    pub fn draw(&mut self, time: f64) {
+        self.log.retain(|entry| time - entry.time < LOG_DURATION);
+        let font_size = 20.0;
+        let mut y = screen_height() - 10.0;
+        for entry in self.log.iter().rev() {
+            let color = if entry.is_error {
+                color_u8!(255, 90, 90, 255)
+            } else {
+                color_u8!(200, 230, 200, 255)
+            };
+            draw_text(&entry.message, 10.0, y, font_size, color);
+            y -= font_size;
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
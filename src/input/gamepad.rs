@@ -0,0 +1,72 @@
+use gilrs::{Axis, Button, Gilrs};
+use macroquad::math::vec2;
+
+use crate::input::{stick_buttons, Backend, Binding, Bindings, PadButton};
+
+const BUTTONS: [(Button, PadButton); 14] = [
+    (Button::South, PadButton::South),
+    (Button::East, PadButton::East),
+    (Button::West, PadButton::West),
+    (Button::North, PadButton::North),
+    (Button::LeftTrigger, PadButton::LeftBumper),
+    (Button::RightTrigger, PadButton::RightBumper),
+    (Button::LeftTrigger2, PadButton::LeftTrigger),
+    (Button::RightTrigger2, PadButton::RightTrigger),
+    (Button::Select, PadButton::Select),
+    (Button::Start, PadButton::Start),
+    (Button::DPadUp, PadButton::DPadUp),
+    (Button::DPadDown, PadButton::DPadDown),
+    (Button::DPadLeft, PadButton::DPadLeft),
+    (Button::DPadRight, PadButton::DPadRight),
+];
+
+// Every connected gamepad, they all control the same player.
+pub struct Gamepads {
+    // None when the platform has no gamepad support.
+    gilrs: Option<Gilrs>,
+}
+
+impl Gamepads {
+    pub fn new() -> Self {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(err) => {
+                eprintln!("ERROR: Gamepads are not available: {err}");
+                None
+            }
+        };
+        Self { gilrs }
+    }
+}
+
+impl Backend for Gamepads {
+    fn poll(&mut self, bindings: &Bindings) -> Vec<Binding> {
+        let Some(gilrs) = &mut self.gilrs else {
+            return Vec::new();
+        };
+        // Gamepad states only update while the events are read.
+        while let Some(event) = gilrs.next_event() {
+            if event.event == gilrs::EventType::Connected {
+                println!("Gamepad connected: {}", gilrs.gamepad(event.id).name());
+            }
+        }
+        let mut held = Vec::new();
+        for (_, gamepad) in gilrs.gamepads() {
+            for (button, pad_button) in BUTTONS {
+                if gamepad.is_pressed(button) {
+                    held.push(Binding::Pad(pad_button));
+                }
+            }
+            let stick = vec2(
+                gamepad.value(Axis::LeftStickX),
+                gamepad.value(Axis::LeftStickY),
+            );
+            held.extend(
+                stick_buttons(stick, bindings.dead_zone)
+                    .into_iter()
+                    .map(Binding::Pad),
+            );
+        }
+        held
+    }
+}
@@ -0,0 +1,19 @@
+use macroquad::input::{is_key_down, is_mouse_button_down};
+
+use crate::input::{Backend, Binding, Bindings, KEYS, MOUSE_BUTTONS};
+
+pub struct Keyboard;
+
+impl Backend for Keyboard {
+    fn poll(&mut self, _bindings: &Bindings) -> Vec<Binding> {
+        let keys = KEYS
+            .into_iter()
+            .filter(|key| is_key_down(*key))
+            .map(Binding::Key);
+        let buttons = MOUSE_BUTTONS
+            .into_iter()
+            .filter(|(button, _)| is_mouse_button_down(*button))
+            .map(|(button, _)| Binding::Mouse(button));
+        keys.chain(buttons).collect()
+    }
+}
@@ -0,0 +1,854 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+
+use macroquad::input::{KeyCode, MouseButton};
+use macroquad::math::Vec2;
+
+use crate::overlay::{DebugPanel, Panels};
+
+#[cfg(all(feature = "gamepad", not(target_arch = "wasm32")))]
+mod gamepad;
+mod keyboard;
+mod touch;
+
+// Relative to the data folder, see `storage`.
+pub const BINDINGS_PATH: &str = "bindings.txt";
+
+// What the game reacts to, the keys behind them come from `Bindings`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Dash,
+    Pause,
+    MenuUp,
+    MenuDown,
+    MenuLeft,
+    MenuRight,
+    MenuSelect,
+    // Held for the camera controls and for switching between play, debug and edit.
+    Tools,
+    SwitchMode,
+    CameraPanUp,
+    CameraPanDown,
+    CameraPanLeft,
+    CameraPanRight,
+    CameraZoomIn,
+    CameraZoomOut,
+    TimePause,
+    TimeStep,
+    TimeSlower,
+    TimeFaster,
+    ReplayRecord,
+    ReplayPlay,
+    EditorPlace,
+    EditorRemove,
+    EditorReloadPalette,
+    EditorLayerDown,
+    EditorLayerUp,
+    EditorParallaxDown,
+    EditorParallaxUp,
+    EditorZoneBounds,
+    EditorZoneZoom,
+    EditorZoneTarget,
+    // Plays the level with the player at the mouse.
+    EditorPlayHere,
+    DebugOverlay,
+    DebugPanelNext,
+    DebugPanelToggle,
+}
+
+impl Action {
+    pub const ALL: [Self; 38] = [
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::Jump,
+        Self::Dash,
+        Self::Pause,
+        Self::MenuUp,
+        Self::MenuDown,
+        Self::MenuLeft,
+        Self::MenuRight,
+        Self::MenuSelect,
+        Self::Tools,
+        Self::SwitchMode,
+        Self::CameraPanUp,
+        Self::CameraPanDown,
+        Self::CameraPanLeft,
+        Self::CameraPanRight,
+        Self::CameraZoomIn,
+        Self::CameraZoomOut,
+        Self::TimePause,
+        Self::TimeStep,
+        Self::TimeSlower,
+        Self::TimeFaster,
+        Self::ReplayRecord,
+        Self::ReplayPlay,
+        Self::EditorPlace,
+        Self::EditorRemove,
+        Self::EditorReloadPalette,
+        Self::EditorLayerDown,
+        Self::EditorLayerUp,
+        Self::EditorParallaxDown,
+        Self::EditorParallaxUp,
+        Self::EditorZoneBounds,
+        Self::EditorZoneZoom,
+        Self::EditorZoneTarget,
+        Self::EditorPlayHere,
+        Self::DebugOverlay,
+        Self::DebugPanelNext,
+        Self::DebugPanelToggle,
+    ];
+
+    // The ones the options menu offers to rebind.
+    pub const GAMEPLAY: [Self; 5] = [
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::Jump,
+        Self::Dash,
+        Self::Pause,
+    ];
+
+    #[must_use]
+    pub fn name(self) -> String {
+        format!("{self:?}")
+    }
+
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    fn default_bindings(self) -> Vec<Binding> {
+        use Binding::{Key, Mouse, Pad, Touch};
+        match self {
+            Self::MoveLeft => vec![
+                Key(KeyCode::A),
+                Key(KeyCode::Left),
+                Pad(PadButton::DPadLeft),
+                Pad(PadButton::StickLeft),
+                Touch(TouchButton::Left),
+            ],
+            Self::MoveRight => vec![
+                Key(KeyCode::D),
+                Key(KeyCode::Right),
+                Pad(PadButton::DPadRight),
+                Pad(PadButton::StickRight),
+                Touch(TouchButton::Right),
+            ],
+            Self::Jump => vec![
+                Key(KeyCode::Space),
+                Key(KeyCode::Up),
+                Pad(PadButton::South),
+                Touch(TouchButton::Jump),
+            ],
+            Self::Dash => vec![
+                Key(KeyCode::LeftShift),
+                Pad(PadButton::West),
+                Pad(PadButton::RightBumper),
+                Touch(TouchButton::Dash),
+            ],
+            Self::Pause => vec![
+                Key(KeyCode::Escape),
+                Pad(PadButton::Start),
+                Touch(TouchButton::Pause),
+            ],
+            Self::MenuUp => vec![
+                Key(KeyCode::Up),
+                Key(KeyCode::W),
+                Pad(PadButton::DPadUp),
+                Pad(PadButton::StickUp),
+                Touch(TouchButton::Jump),
+            ],
+            Self::MenuDown => vec![
+                Key(KeyCode::Down),
+                Key(KeyCode::S),
+                Pad(PadButton::DPadDown),
+                Pad(PadButton::StickDown),
+                Touch(TouchButton::Dash),
+            ],
+            Self::MenuLeft => vec![
+                Key(KeyCode::Left),
+                Key(KeyCode::A),
+                Pad(PadButton::DPadLeft),
+                Pad(PadButton::StickLeft),
+                Touch(TouchButton::Left),
+            ],
+            Self::MenuRight => vec![
+                Key(KeyCode::Right),
+                Key(KeyCode::D),
+                Pad(PadButton::DPadRight),
+                Pad(PadButton::StickRight),
+                Touch(TouchButton::Right),
+            ],
+            Self::MenuSelect => vec![
+                Key(KeyCode::Enter),
+                Key(KeyCode::Space),
+                Pad(PadButton::South),
+                Touch(TouchButton::Select),
+            ],
+            Self::Tools => vec![Key(KeyCode::LeftControl)],
+            Self::SwitchMode => vec![Key(KeyCode::Tab)],
+            Self::CameraPanUp => vec![Key(KeyCode::I)],
+            Self::CameraPanDown => vec![Key(KeyCode::K)],
+            Self::CameraPanLeft => vec![Key(KeyCode::J)],
+            Self::CameraPanRight => vec![Key(KeyCode::L)],
+            Self::CameraZoomIn => vec![Key(KeyCode::PageDown), Key(KeyCode::E)],
+            Self::CameraZoomOut => vec![Key(KeyCode::PageUp), Key(KeyCode::Q)],
+            Self::TimePause => vec![Key(KeyCode::P)],
+            Self::TimeStep => vec![Key(KeyCode::N)],
+            Self::TimeSlower | Self::EditorParallaxDown => vec![Key(KeyCode::Minus)],
+            Self::TimeFaster | Self::EditorParallaxUp => vec![Key(KeyCode::Equal)],
+            Self::ReplayRecord => vec![Key(KeyCode::F9)],
+            Self::ReplayPlay => vec![Key(KeyCode::F10)],
+            Self::EditorPlace => vec![Mouse(MouseButton::Left)],
+            Self::EditorRemove => vec![Mouse(MouseButton::Right)],
+            Self::EditorReloadPalette => vec![Key(KeyCode::Key1)],
+            Self::EditorLayerDown => vec![Key(KeyCode::LeftBracket)],
+            Self::EditorLayerUp => vec![Key(KeyCode::RightBracket)],
+            Self::EditorZoneBounds => vec![Key(KeyCode::B)],
+            Self::EditorZoneZoom => vec![Key(KeyCode::Z)],
+            Self::EditorZoneTarget => vec![Key(KeyCode::T)],
+            Self::EditorPlayHere => vec![Key(KeyCode::F5)],
+            Self::DebugOverlay => vec![Key(KeyCode::F3)],
+            Self::DebugPanelNext => vec![Key(KeyCode::F4)],
+            Self::DebugPanelToggle => vec![Key(KeyCode::F2)],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Pad(PadButton),
+    Touch(TouchButton),
+}
+
+impl Binding {
+    pub fn parse(name: &str) -> Option<Self> {
+        if let Some(button) = name.strip_prefix("Pad") {
+            return PadButton::ALL
+                .into_iter()
+                .find(|other| format!("{other:?}") == button)
+                .map(Self::Pad);
+        }
+        if let Some(button) = name.strip_prefix("Touch") {
+            return TouchButton::ALL
+                .into_iter()
+                .find(|other| format!("{other:?}") == button)
+                .map(Self::Touch);
+        }
+        MOUSE_BUTTONS
+            .into_iter()
+            .find(|(_, button_name)| *button_name == name)
+            .map(|(button, _)| Self::Mouse(button))
+            .or_else(|| {
+                KEYS.into_iter()
+                    .find(|key| format!("{key:?}") == name)
+                    .map(Self::Key)
+            })
+    }
+}
+
+impl Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Key(key) => write!(f, "{key:?}"),
+            Self::Mouse(button) => {
+                let name = MOUSE_BUTTONS
+                    .into_iter()
+                    .find(|(other, _)| other == button)
+                    .map_or("MouseUnknown", |(_, name)| name);
+                write!(f, "{name}")
+            }
+            Self::Pad(button) => write!(f, "Pad{button:?}"),
+            Self::Touch(button) => write!(f, "Touch{button:?}"),
+        }
+    }
+}
+
+// Gamepad buttons by their place on the pad, so they mean the same on every
+// brand. The left stick counts as four more buttons once it leaves the dead zone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    StickUp,
+    StickDown,
+    StickLeft,
+    StickRight,
+}
+
+impl PadButton {
+    pub const ALL: [Self; 18] = [
+        Self::South,
+        Self::East,
+        Self::West,
+        Self::North,
+        Self::LeftBumper,
+        Self::RightBumper,
+        Self::LeftTrigger,
+        Self::RightTrigger,
+        Self::Select,
+        Self::Start,
+        Self::DPadUp,
+        Self::DPadDown,
+        Self::DPadLeft,
+        Self::DPadRight,
+        Self::StickUp,
+        Self::StickDown,
+        Self::StickLeft,
+        Self::StickRight,
+    ];
+}
+
+// The on-screen controls of the touch backend.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TouchButton {
+    Left,
+    Right,
+    Jump,
+    Dash,
+    Pause,
+    Select,
+}
+
+impl TouchButton {
+    pub const ALL: [Self; 6] = [
+        Self::Left,
+        Self::Right,
+        Self::Jump,
+        Self::Dash,
+        Self::Pause,
+        Self::Select,
+    ];
+}
+
+// Stick directions past the dead zone, y points up like on the pads.
+#[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+pub fn stick_buttons(stick: Vec2, dead_zone: f32) -> Vec<PadButton> {
+    let mut buttons = Vec::new();
+    if stick.length() < dead_zone {
+        return buttons;
+    }
+    if stick.x <= -dead_zone {
+        buttons.push(PadButton::StickLeft);
+    } else if stick.x >= dead_zone {
+        buttons.push(PadButton::StickRight);
+    }
+    if stick.y >= dead_zone {
+        buttons.push(PadButton::StickUp);
+    } else if stick.y <= -dead_zone {
+        buttons.push(PadButton::StickDown);
+    }
+    buttons
+}
+
+// Something that holds bindings down: the keyboard and mouse, gamepads or
+// the touch screen.
+pub trait Backend {
+    // Everything held this frame.
+    fn poll(&mut self, bindings: &Bindings) -> Vec<Binding>;
+
+    // On top of everything, with the default camera.
+    fn draw(&self) {}
+}
+
+// Every action can have several bindings, any of them triggers it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bindings {
+    actions: HashMap<Action, Vec<Binding>>,
+    // How far a stick moves before it counts, 0 to 1.
+    pub dead_zone: f32,
+}
+
+impl Bindings {
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.actions.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    // The first binding, for hints.
+    pub fn label(&self, action: Action) -> String {
+        self.get(action)
+            .first()
+            .map_or_else(|| "unbound".to_owned(), Binding::to_string)
+    }
+
+    pub fn set(&mut self, action: Action, bindings: Vec<Binding>) {
+        self.actions.insert(action, bindings);
+    }
+
+    // Replaces the binding at `slot`, a slot past the end adds one.
+    pub fn set_slot(&mut self, action: Action, slot: usize, binding: Binding) {
+        let bindings = self.actions.entry(action).or_default();
+        let slot = if let Some(old) = bindings.get_mut(slot) {
+            *old = binding;
+            slot
+        } else {
+            bindings.push(binding);
+            bindings.len().saturating_sub(1)
+        };
+        // Another slot with the same binding is dropped, after the
+        // replacement so the slot numbers don't shift under it.
+        let mut index = 0;
+        bindings.retain(|other| {
+            let keep = index == slot || *other != binding;
+            index += 1;
+            keep
+        });
+    }
+
+    // `Bind Jump Space PadSouth` lines and a `DeadZone 0.3` line, actions
+    // that are not mentioned keep their defaults. Broken lines are reported
+    // and skipped.
+    pub fn parse(contents: &str) -> Self {
+        let mut bindings = Self::default();
+        for (number, line) in contents.lines().enumerate() {
+            let mut tokens = line.split_ascii_whitespace();
+            match tokens.next() {
+                Some("Bind") => (),
+                Some("DeadZone") => {
+                    match tokens.next().map(str::parse::<f32>) {
+                        Some(Ok(dead_zone)) => bindings.dead_zone = dead_zone.clamp(0.0, 1.0),
+                        _ => eprintln!("ERROR: {}:{}: Bad dead zone", BINDINGS_PATH, number + 1),
+                    }
+                    continue;
+                }
+                _ => continue,
+            }
+            let Some(action) = tokens.next().and_then(Action::from_name) else {
+                eprintln!("ERROR: {}:{}: Unknown action", BINDINGS_PATH, number + 1);
+                continue;
+            };
+            let parsed = tokens
+                .filter_map(|name| {
+                    let binding = Binding::parse(name);
+                    if binding.is_none() {
+                        eprintln!(
+                            "ERROR: {}:{}: Unknown key {}",
+                            BINDINGS_PATH,
+                            number + 1,
+                            name
+                        );
+                    }
+                    binding
+                })
+                .collect();
+            bindings.set(action, parsed);
+        }
+        bindings
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            actions: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_bindings()))
+                .collect(),
+            dead_zone: DEFAULT_DEAD_ZONE,
+        }
+    }
+}
+
+impl Display for Bindings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "DeadZone {}", self.dead_zone)?;
+        for action in Action::ALL {
+            write!(f, "Bind {}", action.name())?;
+            for binding in self.get(action) {
+                write!(f, " {binding}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// Action states for the frame, read from every backend once at its start.
+pub struct Input {
+    pub bindings: Bindings,
+    backends: Vec<Box<dyn Backend>>,
+    held: HashSet<Binding>,
+    previous_held: HashSet<Binding>,
+    down: HashSet<Action>,
+    previous: HashSet<Action>,
+}
+
+impl Input {
+    // The keyboard and mouse, the touch screen and gamepads when built with
+    // the `gamepad` feature.
+    pub fn new(bindings: Bindings) -> Self {
+        #[allow(unused_mut)]
+        let mut backends: Vec<Box<dyn Backend>> = vec![
+            Box::new(keyboard::Keyboard),
+            Box::new(touch::Touch::default()),
+        ];
+        #[cfg(all(feature = "gamepad", not(target_arch = "wasm32")))]
+        backends.push(Box::new(gamepad::Gamepads::new()));
+        #[cfg(all(not(feature = "gamepad"), not(target_arch = "wasm32")))]
+        println!("Gamepad support is not compiled in, build with --features gamepad");
+        Self::with_backends(bindings, backends)
+    }
+
+    pub fn with_backends(bindings: Bindings, backends: Vec<Box<dyn Backend>>) -> Self {
+        Self {
+            bindings,
+            backends,
+            held: HashSet::new(),
+            previous_held: HashSet::new(),
+            down: HashSet::new(),
+            previous: HashSet::new(),
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.previous_held = std::mem::take(&mut self.held);
+        for backend in &mut self.backends {
+            self.held.extend(backend.poll(&self.bindings));
+        }
+        self.previous = std::mem::take(&mut self.down);
+        for action in Action::ALL {
+            if self
+                .bindings
+                .get(action)
+                .iter()
+                .any(|binding| self.held.contains(binding))
+            {
+                self.down.insert(action);
+            }
+        }
+    }
+
+    pub fn draw(&self) {
+        for backend in &self.backends {
+            backend.draw();
+        }
+    }
+
+    pub fn down(&self, action: Action) -> bool {
+        self.down.contains(&action)
+    }
+
+    // Only on the frame the action starts.
+    pub fn pressed(&self, action: Action) -> bool {
+        self.down.contains(&action) && !self.previous.contains(&action)
+    }
+
+    pub fn released(&self, action: Action) -> bool {
+        !self.down.contains(&action) && self.previous.contains(&action)
+    }
+
+    // Everything held on any backend, sorted by name.
+    pub fn held(&self) -> Vec<Binding> {
+        let mut held: Vec<Binding> = self.held.iter().copied().collect();
+        held.sort_by_cached_key(ToString::to_string);
+        held
+    }
+
+    // A key or button that started being held this frame, for rebinding.
+    pub fn pressed_binding(&self) -> Option<Binding> {
+        self.held.difference(&self.previous_held).next().copied()
+    }
+
+    pub fn register_panels<'a>(&'a self, panels: &mut Panels<'a>) {
+        panels.register("input", self);
+    }
+}
+
+impl DebugPanel for Input {
+    fn lines(&self) -> Vec<String> {
+        let held: Vec<String> = self.held().iter().map(ToString::to_string).collect();
+        vec![format!("held: {}", held.join(" "))]
+    }
+}
+
+const DEFAULT_DEAD_ZONE: f32 = 0.25;
+
+pub const MOUSE_BUTTONS: [(MouseButton, &str); 3] = [
+    (MouseButton::Left, "MouseLeft"),
+    (MouseButton::Right, "MouseRight"),
+    (MouseButton::Middle, "MouseMiddle"),
+];
+
+// Keys that can be bound, names are the `KeyCode` variants.
+pub const KEYS: [KeyCode; 76] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::LeftShift,
+    KeyCode::RightShift,
+    KeyCode::LeftControl,
+    KeyCode::RightControl,
+    KeyCode::LeftAlt,
+    KeyCode::RightAlt,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::Apostrophe,
+    KeyCode::Semicolon,
+    KeyCode::Slash,
+    KeyCode::Backslash,
+    KeyCode::LeftBracket,
+    KeyCode::RightBracket,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+];
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use macroquad::math::vec2;
+
+    use super::*;
+
+    // Holds scripted bindings, one set per frame.
+    struct Fake {
+        frames: VecDeque<Vec<Binding>>,
+    }
+
+    impl Fake {
+        fn boxed(frames: Vec<Vec<Binding>>) -> Box<dyn Backend> {
+            Box::new(Self {
+                frames: frames.into(),
+            })
+        }
+    }
+
+    impl Backend for Fake {
+        fn poll(&mut self, _bindings: &Bindings) -> Vec<Binding> {
+            self.frames.pop_front().unwrap_or_default()
+        }
+    }
+
+    const SOUTH: Binding = Binding::Pad(PadButton::South);
+    const SPACE: Binding = Binding::Key(KeyCode::Space);
+
+    #[test]
+    fn edges_last_one_frame() {
+        let fake = Fake::boxed(vec![vec![SPACE], vec![SPACE], vec![]]);
+        let mut input = Input::with_backends(Bindings::default(), vec![fake]);
+        input.update();
+        assert!(input.pressed(Action::Jump));
+        assert!(input.down(Action::Jump));
+        input.update();
+        assert!(!input.pressed(Action::Jump));
+        assert!(input.down(Action::Jump));
+        input.update();
+        assert!(input.released(Action::Jump));
+        assert!(!input.down(Action::Jump));
+    }
+
+    #[test]
+    fn pad_south_jumps() {
+        let fake = Fake::boxed(vec![vec![SOUTH]]);
+        let mut input = Input::with_backends(Bindings::default(), vec![fake]);
+        input.update();
+        assert!(input.pressed(Action::Jump));
+        assert!(input.pressed(Action::MenuSelect));
+        assert!(!input.down(Action::Dash));
+    }
+
+    #[test]
+    fn backends_combine() {
+        let keyboard = Fake::boxed(vec![vec![SPACE], vec![]]);
+        let pad = Fake::boxed(vec![vec![], vec![SOUTH]]);
+        let mut input = Input::with_backends(Bindings::default(), vec![keyboard, pad]);
+        input.update();
+        assert!(input.pressed(Action::Jump));
+        // Handing the jump from one device to another holds it.
+        input.update();
+        assert!(input.down(Action::Jump));
+        assert!(!input.pressed(Action::Jump));
+        assert!(!input.released(Action::Jump));
+        assert_eq!(input.pressed_binding(), Some(SOUTH));
+    }
+
+    #[test]
+    fn stick_dead_zone() {
+        assert!(stick_buttons(vec2(0.2, -0.1), 0.25).is_empty());
+        assert_eq!(
+            stick_buttons(vec2(-0.9, 0.1), 0.25),
+            vec![PadButton::StickLeft]
+        );
+        assert_eq!(
+            stick_buttons(vec2(0.6, 0.6), 0.25),
+            vec![PadButton::StickRight, PadButton::StickUp]
+        );
+        assert_eq!(
+            stick_buttons(vec2(0.0, -0.3), 0.25),
+            vec![PadButton::StickDown]
+        );
+    }
+
+    // Actions that are read in the same state must not share a default.
+    #[test]
+    fn defaults_dont_overlap() {
+        use Action::*;
+        let shared = [
+            Tools,
+            SwitchMode,
+            CameraPanUp,
+            CameraPanDown,
+            CameraPanLeft,
+            CameraPanRight,
+            CameraZoomIn,
+            CameraZoomOut,
+            DebugOverlay,
+            DebugPanelNext,
+            DebugPanelToggle,
+        ];
+        let play = [
+            MoveLeft,
+            MoveRight,
+            Jump,
+            Dash,
+            Pause,
+            TimePause,
+            TimeStep,
+            TimeSlower,
+            TimeFaster,
+            ReplayRecord,
+            ReplayPlay,
+        ];
+        let edit = [
+            EditorPlace,
+            EditorRemove,
+            EditorReloadPalette,
+            EditorLayerDown,
+            EditorLayerUp,
+            EditorParallaxDown,
+            EditorParallaxUp,
+            EditorZoneBounds,
+            EditorZoneZoom,
+            EditorZoneTarget,
+            EditorPlayHere,
+        ];
+        let bindings = Bindings::default();
+        for state in [play, edit] {
+            let mut seen = HashMap::new();
+            for action in shared.into_iter().chain(state) {
+                for binding in bindings.get(action) {
+                    let other = seen.insert(*binding, action);
+                    assert!(other.is_none(), "{binding} is {other:?} and {action:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rebinds_one_slot() {
+        let mut bindings = Bindings::default();
+        let (x, up) = (Binding::Key(KeyCode::X), Binding::Key(KeyCode::Up));
+        let defaults = bindings.get(Action::Jump).to_vec();
+        bindings.set_slot(Action::Jump, 0, x);
+        assert_eq!(bindings.get(Action::Jump).first(), Some(&x));
+        assert_eq!(bindings.get(Action::Jump).get(1..), defaults.get(1..));
+        bindings.set_slot(Action::Jump, defaults.len(), Binding::Key(KeyCode::C));
+        assert_eq!(bindings.get(Action::Jump).len(), defaults.len() + 1);
+        // Binding what another slot already has moves it.
+        bindings.set_slot(Action::Jump, 0, up);
+        assert_eq!(bindings.get(Action::Jump).first(), Some(&up));
+        assert_eq!(bindings.get(Action::Jump).len(), defaults.len());
+    }
+
+    #[test]
+    fn rebinding_to_an_earlier_slot_keeps_the_rest() {
+        let mut bindings = Bindings::default();
+        let defaults = bindings.get(Action::Jump).to_vec();
+        let first = *defaults.first().expect("Jump has default bindings");
+        bindings.set_slot(Action::Jump, 1, first);
+        assert_eq!(bindings.get(Action::Jump).first(), Some(&first));
+        assert_eq!(bindings.get(Action::Jump).get(1..), defaults.get(2..));
+    }
+
+    #[test]
+    fn bindings_round_trip() {
+        let mut bindings = Bindings {
+            dead_zone: 0.4,
+            ..Bindings::default()
+        };
+        bindings.set(
+            Action::Dash,
+            vec![
+                Binding::Key(KeyCode::X),
+                Binding::Mouse(MouseButton::Right),
+                Binding::Pad(PadButton::RightTrigger),
+                Binding::Touch(TouchButton::Dash),
+            ],
+        );
+        assert_eq!(Bindings::parse(&bindings.to_string()), bindings);
+    }
+}
@@ -0,0 +1,80 @@
+use macroquad::prelude::*;
+
+use crate::input::{Backend, Binding, Bindings, TouchButton};
+
+// Fraction of the shorter screen side.
+const BUTTON_SIZE: f32 = 0.18;
+const MARGIN: f32 = 0.04;
+
+// On-screen buttons. They stay hidden until the screen is first touched, so
+// they never cover the game on desktops.
+#[derive(Default)]
+pub struct Touch {
+    used: bool,
+    held: Vec<TouchButton>,
+}
+
+impl Backend for Touch {
+    fn poll(&mut self, _bindings: &Bindings) -> Vec<Binding> {
+        let touches = touches();
+        self.used |= !touches.is_empty();
+        self.held = TouchButton::ALL
+            .into_iter()
+            .filter(|button| {
+                let rect = button_rect(*button);
+                touches.iter().any(|touch| {
+                    !matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled)
+                        && rect.contains(touch.position)
+                })
+            })
+            .collect();
+        self.held.iter().copied().map(Binding::Touch).collect()
+    }
+
+    fn draw(&self) {
+        if !self.used {
+            return;
+        }
+        for button in TouchButton::ALL {
+            let rect = button_rect(button);
+            let alpha = if self.held.contains(&button) { 160 } else { 60 };
+            draw_rectangle(
+                rect.x,
+                rect.y,
+                rect.w,
+                rect.h,
+                color_u8!(255, 255, 255, alpha),
+            );
+            let label = format!("{button:?}");
+            let font_size = rect.h * 0.3;
+            // Measured at a whole size and scaled to the real one.
+            let size = measure_text(&label, None, 30, font_size / 30.0);
+            draw_text(
+                &label,
+                rect.center().x - size.width / 2.0,
+                rect.center().y + size.height / 2.0,
+                font_size,
+                color_u8!(0, 0, 0, 200),
+            );
+        }
+    }
+}
+
+// Movement in the bottom left, actions in the bottom right, pause and
+// select in the top corners.
+fn button_rect(button: TouchButton) -> Rect {
+    let shorter = screen_width().min(screen_height());
+    let size = shorter * BUTTON_SIZE;
+    let margin = shorter * MARGIN;
+    let bottom = screen_height() - margin - size;
+    let right = screen_width() - margin - size;
+    let (x, y) = match button {
+        TouchButton::Left => (margin, bottom),
+        TouchButton::Right => (margin.mul_add(2.0, size), bottom),
+        TouchButton::Jump => (right, bottom),
+        TouchButton::Dash => (right - margin - size, bottom),
+        TouchButton::Pause => (right, margin),
+        TouchButton::Select => (margin, margin),
+    };
+    Rect::new(x, y, size, size)
+}
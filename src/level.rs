@@ -0,0 +1,237 @@
+use std::fmt::{self, Display};
+
+use macroquad::math::{Rect, Vec2};
+
+use crate::camera_zone::CameraZone;
+use crate::checkpoint::Checkpoint;
+use crate::emitter::Emitter;
+use crate::static_layers::{LayerSettings, StaticEntity, StaticLayers};
+use crate::trigger::Trigger;
+
+// Everything a level file describes, as the editor authors it.
+#[derive(Clone)]
+pub struct Level {
+    pub static_layers: StaticLayers,
+    pub triggers: Vec<Trigger>,
+    pub checkpoints: Vec<Checkpoint>,
+    pub emitters: Vec<Emitter>,
+    pub camera_zones: Vec<CameraZone>,
+    // The camera never shows anything outside.
+    pub bounds: Option<Rect>,
+    // Sample id of the track that loops while the level is played.
+    pub music: Option<String>,
+}
+
+impl Level {
+    pub fn new() -> Self {
+        Self {
+            static_layers: StaticLayers::new(),
+            triggers: Vec::new(),
+            checkpoints: Vec::new(),
+            emitters: Vec::new(),
+            camera_zones: Vec::new(),
+            bounds: None,
+            music: None,
+        }
+    }
+
+    // `source` names the file in errors.
+    pub fn parse(contents: &str, source: &str) -> Result<Self, String> {
+        let mut level = Self::new();
+        let mut z_index = 0;
+        for (number, line) in contents.lines().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            let at_line = |why: String| format!("{}:{}: {}", source, number + 1, why);
+            let (token, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match token {
+                "StaticLayer" => {
+                    let (z, settings) = rest.split_once(':').unwrap_or((rest, ""));
+                    z_index = z
+                        .trim()
+                        .parse()
+                        .map_err(|_| at_line(format!("Could not parse the z-index {z}")))?;
+                    let settings = LayerSettings::parse(settings).map_err(at_line)?;
+                    level.static_layers.set_layer_settings(z_index, settings);
+                    println!("Parsed beginning of the new StaticLayer {z_index}");
+                }
+                "StaticEntity" => {
+                    let entity = StaticEntity::parse(rest).map_err(at_line)?;
+                    println!("parsed {entity}");
+                    level.static_layers.add_entity(z_index, entity);
+                }
+                "Trigger" => {
+                    let trigger = Trigger::parse(rest)
+                        .ok_or_else(|| at_line("Could not parse the trigger".to_owned()))?;
+                    println!("parsed {trigger}");
+                    level.triggers.push(trigger);
+                }
+                "Checkpoint" => {
+                    let checkpoint = Checkpoint::parse(rest)
+                        .ok_or_else(|| at_line("Could not parse the checkpoint".to_owned()))?;
+                    println!("parsed {checkpoint}");
+                    level.checkpoints.push(checkpoint);
+                }
+                "Emitter" => {
+                    let emitter = Emitter::parse(rest)
+                        .ok_or_else(|| at_line("Could not parse the emitter".to_owned()))?;
+                    println!("parsed {emitter}");
+                    level.emitters.push(emitter);
+                }
+                "CameraZone" => {
+                    let zone = CameraZone::parse(rest)
+                        .ok_or_else(|| at_line("Could not parse the camera zone".to_owned()))?;
+                    println!("parsed {zone}");
+                    level.camera_zones.push(zone);
+                }
+                "Bounds" => {
+                    level.bounds = Some(
+                        parse_rect(rest)
+                            .ok_or_else(|| at_line("Could not parse the bounds".to_owned()))?,
+                    );
+                }
+                "Music" => level.music = Some(rest.trim().to_owned()),
+                _ => (),
+            }
+        }
+        Ok(level)
+    }
+
+    // The innermost camera zone at `point`, the last one wins when zones
+    // overlap.
+    pub fn zone_at(&self, point: Vec2) -> Option<usize> {
+        self.camera_zones
+            .iter()
+            .rposition(|zone| zone.contains(point))
+    }
+}
+
+// Parses the inside of `Bounds { x:0, y:0, w:800, h:600 }`, every field is
+// needed.
+fn parse_rect(rest: &str) -> Option<Rect> {
+    let (mut x, mut y, mut w, mut h) = (None, None, None, None);
+    for pair in rest
+        .split_ascii_whitespace()
+        .filter(|e| e != &"{" && e != &"}")
+    {
+        let (key, value) = pair.trim_matches(',').split_once(':')?;
+        let value = Some(value.parse().ok()?);
+        match key {
+            "x" => x = value,
+            "y" => y = value,
+            "w" => w = value,
+            "h" => h = value,
+            _ => return None,
+        }
+    }
+    Some(Rect::new(x?, y?, w?, h?))
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(music) = &self.music {
+            writeln!(f, "Music {music}")?;
+        }
+        if let Some(Rect { x, y, w, h }) = self.bounds {
+            writeln!(f, "Bounds {{ x:{x}, y:{y}, w:{w}, h:{h} }}")?;
+        }
+        write!(f, "{}", self.static_layers)?;
+        for trigger in &self.triggers {
+            writeln!(f, "{trigger}")?;
+        }
+        for checkpoint in &self.checkpoints {
+            writeln!(f, "{checkpoint}")?;
+        }
+        for emitter in &self.emitters {
+            writeln!(f, "{emitter}")?;
+        }
+        for zone in &self.camera_zones {
+            writeln!(f, "{zone}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::math::vec2;
+
+    use super::*;
+    use crate::manifest::{AssetKind, Manifest};
+
+    #[test]
+    fn bounds_are_a_plain_rect() {
+        let level = Level::parse("Bounds { x:-10, y:-20, w:30, h:40 }", "bounds.txt");
+        assert_eq!(
+            level.map(|level| level.bounds),
+            Ok(Some(Rect::new(-10.0, -20.0, 30.0, 40.0)))
+        );
+        for bounds in [
+            "Bounds { x:0, y:0, w:10, h:10, zoom:2 }",
+            "Bounds { x:0, y:0, w:10, h:10, bounds }",
+            "Bounds { x:0, y:0, w:10 }",
+        ] {
+            assert!(Level::parse(bounds, "bounds.txt").is_err(), "{bounds}");
+        }
+    }
+
+    #[test]
+    fn keeps_settings_of_empty_layers() {
+        let contents = "StaticLayer -3: parallax:0.2 scale:0.5\n\n\
+                        StaticLayer 0:\n\
+                        StaticEntity { platform1, x:0, y:0 }\n\n";
+        let level = Level::parse(contents, "layers.txt").expect("the layers should parse");
+        let saved = level.to_string();
+        assert!(
+            saved.contains("StaticLayer -3: parallax:0.2 scale:0.5"),
+            "{saved}"
+        );
+        let reparsed = Level::parse(&saved, "layers.txt").expect("a saved level should parse");
+        assert_eq!(reparsed.to_string(), saved);
+    }
+
+    #[test]
+    fn layer_scale_must_be_positive() {
+        for scale in ["0", "-0.5"] {
+            let contents = format!("StaticLayer -3: scale:{scale}\n");
+            let Err(error) = Level::parse(&contents, "layers.txt") else {
+                panic!("scale:{scale} should not parse");
+            };
+            assert!(error.contains("scale must be positive"), "{error}");
+        }
+    }
+
+    #[test]
+    fn the_last_zone_wins() {
+        let contents = "CameraZone { x:0, y:0, w:100, h:100 }\n\
+                        CameraZone { x:50, y:50, w:20, h:20, zoom:2 }\n";
+        let level = Level::parse(contents, "zones.txt").expect("the zones should parse");
+        assert_eq!(level.zone_at(vec2(60.0, 60.0)), Some(1));
+        assert_eq!(level.zone_at(vec2(10.0, 10.0)), Some(0));
+        assert_eq!(level.zone_at(vec2(200.0, 10.0)), None);
+    }
+
+    #[test]
+    fn shipped_level_plays_music() {
+        let level = Level::parse(include_str!("../data/level0.txt"), "level0.txt")
+            .expect("data/level0.txt is valid");
+        let music = level.music.as_deref().expect("level0 declares music");
+        let manifest = Manifest::parse(include_str!("../assets/manifest.txt"))
+            .expect("assets/manifest.txt is valid");
+        assert!(
+            manifest
+                .entries
+                .iter()
+                .any(|entry| entry.kind == AssetKind::Sound && entry.id == music),
+            "{music} is not a sound"
+        );
+        assert!(level.to_string().starts_with(&format!("Music {music}\n")));
+    }
+}
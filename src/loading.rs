@@ -0,0 +1,270 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use macroquad::file::{load_file, load_string};
+use macroquad::prelude::*;
+
+use crate::audio::{Audio, Sample};
+use crate::diagnostics::Diagnostics;
+use crate::manifest::{AssetEntry, AssetKind, Manifest};
+use crate::sprite::{decode_png, Sprites};
+
+type Job = Pin<Box<dyn Future<Output = Loaded>>>;
+
+// Seconds spent polling loads per frame, at least one is polled.
+const FRAME_BUDGET: f64 = 0.008;
+
+enum Loaded {
+    Manifest(Result<Manifest, String>),
+    Sprite(AssetEntry, Result<Image, String>),
+    Clips(AssetEntry, Result<String, String>),
+    Cues(AssetEntry, Result<String, String>),
+    Sound(AssetEntry, Result<Sample, String>),
+}
+
+// Runs before the world exists. Every asset load is started at once and
+// polled a few at a time each frame, so the window keeps drawing while files
+// arrive.
+pub struct Loading {
+    strict: bool,
+    jobs: Vec<(String, Job)>,
+    total: usize,
+    sprites: Vec<(AssetEntry, Result<Image, String>)>,
+    clips: Vec<(AssetEntry, Result<String, String>)>,
+    cues: Vec<(AssetEntry, Result<String, String>)>,
+    sounds: Vec<(AssetEntry, Result<Sample, String>)>,
+    failures: Vec<String>,
+    // A broken manifest leaves nothing to load, the game stays on this screen
+    // unless assets are strict.
+    fatal: Option<String>,
+    acknowledged: bool,
+}
+
+impl Loading {
+    pub fn new(strict: bool) -> Self {
+        let mut loading = Self::empty(strict);
+        loading.start(crate::manifest::MANIFEST_PATH.to_owned(), async {
+            Loaded::Manifest(Manifest::load().await)
+        });
+        loading
+    }
+
+    const fn empty(strict: bool) -> Self {
+        Self {
+            strict,
+            jobs: Vec::new(),
+            total: 0,
+            sprites: Vec::new(),
+            clips: Vec::new(),
+            cues: Vec::new(),
+            sounds: Vec::new(),
+            failures: Vec::new(),
+            fatal: None,
+            acknowledged: false,
+        }
+    }
+
+    fn start(&mut self, name: String, job: impl Future<Output = Loaded> + 'static) {
+        self.jobs.push((name, Box::pin(job)));
+        self.total += 1;
+    }
+
+    fn start_assets(&mut self, manifest: &Manifest) {
+        for entry in manifest.entries.iter().cloned() {
+            let name = entry.path.clone();
+            match entry.kind {
+                AssetKind::Sprite => self.start(name, async move {
+                    let image = match load_file(&entry.path).await {
+                        Ok(bytes) => decode_png(&entry.path, &bytes),
+                        Err(why) => Err(why.to_string()),
+                    };
+                    Loaded::Sprite(entry, image)
+                }),
+                AssetKind::Clips => self.start(name, async move {
+                    let contents = load_string(&entry.path)
+                        .await
+                        .map_err(|why| why.to_string());
+                    Loaded::Clips(entry, contents)
+                }),
+                AssetKind::Cues => self.start(name, async move {
+                    let contents = load_string(&entry.path)
+                        .await
+                        .map_err(|why| why.to_string());
+                    Loaded::Cues(entry, contents)
+                }),
+                AssetKind::Sound => self.start(name, async move {
+                    let sample = Sample::new(&entry.path).await;
+                    Loaded::Sound(entry, sample)
+                }),
+            }
+        }
+    }
+
+    // Polls unfinished loads until the frame's budget is spent. Native loads
+    // finish on their first poll, so polling all of them at once would skip
+    // straight to the end of the progress bar.
+    pub fn update(&mut self) {
+        let waker = Waker::from(Arc::new(FrameWaker));
+        let mut context = Context::from_waker(&waker);
+        let started = get_time();
+        let mut index = 0;
+        let mut polled = 0;
+        while let Some((_, job)) = self.jobs.get_mut(index) {
+            if polled > 0 && get_time() - started > FRAME_BUDGET {
+                break;
+            }
+            polled += 1;
+            match job.as_mut().poll(&mut context) {
+                Poll::Ready(loaded) => {
+                    drop(self.jobs.remove(index));
+                    self.finish_job(loaded);
+                }
+                Poll::Pending => index += 1,
+            }
+        }
+
+        if self.jobs.is_empty() && !self.failures.is_empty() && is_key_pressed(KeyCode::Enter) {
+            self.acknowledged = true;
+        }
+    }
+
+    fn finish_job(&mut self, loaded: Loaded) {
+        match loaded {
+            Loaded::Manifest(Ok(manifest)) => self.start_assets(&manifest),
+            Loaded::Manifest(Err(why)) => {
+                eprintln!("ERROR: {why}");
+                self.fatal = Some(why);
+            }
+            Loaded::Sprite(entry, image) => {
+                self.failed("sprite", &entry, image.as_ref().err());
+                self.sprites.push((entry, image));
+            }
+            Loaded::Clips(entry, contents) => {
+                self.failed("clips", &entry, contents.as_ref().err());
+                self.clips.push((entry, contents));
+            }
+            Loaded::Cues(entry, contents) => {
+                self.failed("cues", &entry, contents.as_ref().err());
+                self.cues.push((entry, contents));
+            }
+            Loaded::Sound(entry, sample) => {
+                self.failed("sound", &entry, sample.as_ref().err());
+                self.sounds.push((entry, sample));
+            }
+        }
+    }
+
+    fn failed(&mut self, kind: &str, entry: &AssetEntry, why: Option<&String>) {
+        if let Some(why) = why {
+            self.failures
+                .push(format!("Missing {} {}: {}", kind, entry.id, why));
+        }
+    }
+
+    // Failures keep the screen up until they are acknowledged, so they
+    // don't flash by unnoticed. Strict mode is done right away and lets
+    // `finish` fail.
+    pub fn is_done(&self) -> bool {
+        if self.fatal.is_some() {
+            return self.strict;
+        }
+        self.jobs.is_empty() && (self.failures.is_empty() || self.acknowledged || self.strict)
+    }
+
+    pub fn draw(&self) {
+        let font_size = 24.0;
+        let width = screen_width() * 0.6;
+        let x = (screen_width() - width) * 0.5;
+        let y = screen_height() * 0.5;
+        let progress = if self.total == 0 {
+            0.0
+        } else {
+            let count = |jobs: usize| f32::from(u16::try_from(jobs).unwrap_or(u16::MAX));
+            count(self.total - self.jobs.len()) / count(self.total)
+        };
+
+        draw_rectangle_lines(x, y, width, 20.0, 2.0, WHITE);
+        draw_rectangle(x + 2.0, y + 2.0, (width - 4.0) * progress, 16.0, WHITE);
+
+        let mut text_y = y - 10.0;
+        let status = if let Some(why) = &self.fatal {
+            format!("Loading failed: {why}")
+        } else if let Some((name, _)) = self.jobs.first() {
+            format!("Loading {name}")
+        } else if !self.failures.is_empty() {
+            "Some assets are missing, press Enter to continue with placeholders".to_owned()
+        } else {
+            "Done".to_owned()
+        };
+        let color = if self.fatal.is_some() { RED } else { WHITE };
+        draw_text(&status, x, text_y, font_size, color);
+
+        text_y = y + 20.0 + font_size;
+        for failure in &self.failures {
+            draw_text(
+                failure,
+                x,
+                text_y,
+                font_size * 0.8,
+                color_u8!(255, 90, 90, 255),
+            );
+            text_y += font_size * 0.8;
+        }
+    }
+
+    // Fails in strict mode when anything is missing or the manifest is broken.
+    // Sounds load through macroquad's futures, which aren't Send.
+    #[allow(clippy::future_not_send)]
+    pub async fn finish(self) -> Result<(Audio, Sprites), String> {
+        if let Some(why) = self.fatal {
+            return Err(why);
+        }
+        let audio =
+            Audio::from_loaded(self.sounds, self.cues, Diagnostics::new(self.strict)).await?;
+        let sprites =
+            Sprites::from_loaded(self.sprites, self.clips, Diagnostics::new(self.strict))?;
+        Ok((audio, sprites))
+    }
+}
+
+// Jobs are polled every frame anyway, so there is nothing to wake.
+struct FrameWaker;
+
+impl Wake for FrameWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::pin;
+
+    use super::*;
+
+    fn broken_manifest(strict: bool) -> Loading {
+        let mut loading = Loading::empty(strict);
+        loading.finish_job(Loaded::Manifest(Err(
+            "manifest.txt:3: Unknown asset kind Music".to_owned(),
+        )));
+        loading
+    }
+
+    #[test]
+    fn strict_mode_fails_on_a_broken_manifest() {
+        let loading = broken_manifest(true);
+        assert!(loading.is_done());
+        let waker = Waker::from(Arc::new(FrameWaker));
+        let finished = pin!(loading.finish()).poll(&mut Context::from_waker(&waker));
+        assert!(
+            matches!(&finished, Poll::Ready(Err(why)) if why.contains("Unknown asset kind")),
+            "{:?}",
+            finished.map(Result::err)
+        );
+    }
+
+    #[test]
+    fn broken_manifest_keeps_the_screen_up() {
+        assert!(!broken_manifest(false).is_done());
+    }
+}
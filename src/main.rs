@@ -5,7 +5,6 @@
     clippy::unwrap_used,
     clippy::unwrap_in_result,
     clippy::unneeded_field_pattern,
-    clippy::string_to_string,
     clippy::string_slice,
     clippy::string_add,
     clippy::str_to_string,
@@ -33,26 +32,45 @@
 #![allow(
     clippy::cast_precision_loss,
     clippy::missing_panics_doc,
+    clippy::multiple_crate_versions,
     clippy::option_if_let_else,
     clippy::pattern_type_mismatch
 )]
 
 use macroquad::prelude::*;
 
+mod ability;
+mod atlas;
 mod audio;
 mod camera;
+mod camera_effects;
+mod camera_zone;
+mod checkpoint;
 mod collider;
 mod common;
+mod diagnostics;
+mod emitter;
 mod entity;
+#[cfg(not(target_arch = "wasm32"))]
+mod hot_reload;
+mod input;
+mod level;
+mod loading;
+mod manifest;
+mod music;
+mod overlay;
 mod player;
+mod replay;
+mod save;
+mod simulation;
 mod sprite;
 mod static_layers;
+mod storage;
+mod timestep;
+mod trigger;
 mod tween;
 mod world;
 
-pub const SPRITE_PATHS: &str = include_str!("../data/sprites.txt");
-pub const SOUND_PATHS: &str = include_str!("../data/sounds.txt");
-
 fn window_conf() -> Conf {
     Conf {
         window_title: "game".to_owned(),
@@ -63,10 +81,24 @@ fn window_conf() -> Conf {
 
 #[macroquad::main(window_conf)]
 async fn main() {
+    // Macroquad runs the game loop on the main thread, its futures are never sent.
+    #![allow(clippy::future_not_send)]
     set_pc_assets_folder("assets");
 
-    let audio = audio::Audio::new().await;
-    let sprites = sprite::Sprites::new().await;
+    let mut loading = loading::Loading::new(diagnostics::Diagnostics::strict_from_env());
+    while !loading.is_done() {
+        clear_background(color_u8!(35, 47, 54, 255));
+        loading.update();
+        loading.draw();
+        next_frame().await;
+    }
+    let (audio, sprites) = match loading.finish().await {
+        Ok(loaded) => loaded,
+        Err(why) => {
+            eprintln!("ERROR: {why}");
+            std::process::exit(1);
+        }
+    };
     let mut world = world::World::new(audio, sprites);
 
     world.setup();
@@ -74,6 +106,8 @@ async fn main() {
     loop {
         clear_background(color_u8!(35, 47, 54, 255));
 
+        #[cfg(not(target_arch = "wasm32"))]
+        world.hot_reload().await;
         world.input();
         world.update();
         world.draw();
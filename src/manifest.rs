@@ -0,0 +1,214 @@
+#[cfg(not(feature = "embed-manifest"))]
+use macroquad::file::load_string;
+use macroquad::math::Vec2;
+
+use crate::collider::Collider;
+
+// Relative to the assets folder natively and to the page on the web.
+pub const MANIFEST_PATH: &str = "manifest.txt";
+
+#[cfg(feature = "embed-manifest")]
+const EMBEDDED_MANIFEST: &str = include_str!("../assets/manifest.txt");
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssetKind {
+    Sprite,
+    Sound,
+    Clips,
+    Cues,
+}
+
+impl AssetKind {
+    fn parse(token: &str) -> Option<Self> {
+        match token {
+            "Sprite" => Some(Self::Sprite),
+            "Sound" => Some(Self::Sound),
+            "Clips" => Some(Self::Clips),
+            "Cues" => Some(Self::Cues),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetEntry {
+    pub id: String,
+    pub kind: AssetKind,
+    pub path: String,
+    pub pivot: Option<Vec2>,
+    pub collider: Option<Collider>,
+    pub tags: Vec<String>,
+}
+
+impl AssetEntry {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+}
+
+// Lists every asset the game loads, one per line:
+// <Kind> <id> <path> [pivot:x,y] [collider:x,y,w,h] [tags:a,b]
+#[derive(Clone, Debug, Default)]
+pub struct Manifest {
+    pub entries: Vec<AssetEntry>,
+}
+
+impl Manifest {
+    // Only awaits when the manifest is read at runtime.
+    #[cfg_attr(feature = "embed-manifest", allow(clippy::unused_async))]
+    pub async fn load() -> Result<Self, String> {
+        #[cfg(feature = "embed-manifest")]
+        let contents = EMBEDDED_MANIFEST.to_owned();
+        #[cfg(not(feature = "embed-manifest"))]
+        let contents = load_string(MANIFEST_PATH)
+            .await
+            .map_err(|why| format!("couldn't load {MANIFEST_PATH}: {why}"))?;
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut entries = Vec::new();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let entry = Self::parse_entry(line)
+                .map_err(|why| format!("{}:{}: {}", MANIFEST_PATH, number + 1, why))?;
+            if entries
+                .iter()
+                .any(|e: &AssetEntry| e.id == entry.id && e.kind == entry.kind)
+            {
+                return Err(format!(
+                    "{}:{}: Duplicate asset id {}",
+                    MANIFEST_PATH,
+                    number + 1,
+                    entry.id
+                ));
+            }
+            entries.push(entry);
+        }
+        Ok(Self { entries })
+    }
+
+    fn parse_entry(line: &str) -> Result<AssetEntry, String> {
+        let mut tokens = line.split_ascii_whitespace();
+        let kind = tokens.next().unwrap_or_default();
+        let kind = AssetKind::parse(kind).ok_or_else(|| format!("Unknown asset kind {kind}"))?;
+        let id = tokens.next().ok_or("Asset without an id")?.to_owned();
+        let path = tokens.next().ok_or("Asset without a path")?.to_owned();
+        let mut entry = AssetEntry {
+            id,
+            kind,
+            path,
+            pivot: None,
+            collider: None,
+            tags: Vec::new(),
+        };
+        for token in tokens {
+            let (key, value) = token
+                .split_once(':')
+                .ok_or_else(|| format!("Expected key:value, got {token}"))?;
+            match key {
+                "pivot" => {
+                    let [x, y] = parse_floats::<2>(value)?;
+                    entry.pivot = Some(Vec2::new(x, y));
+                }
+                "collider" => entry.collider = Some(Collider::from(parse_floats::<4>(value)?)),
+                "tags" => entry.tags = value.split(',').map(str::to_owned).collect(),
+                _ => return Err(format!("Unknown asset field {key}")),
+            }
+        }
+        Ok(entry)
+    }
+}
+
+fn parse_floats<const N: usize>(value: &str) -> Result<[f32; N], String> {
+    let mut result = [0f32; N];
+    let mut parts = value.split(',');
+    for item in &mut result {
+        *item = parts
+            .next()
+            .and_then(|part| part.parse().ok())
+            .ok_or_else(|| format!("Expected {N} numbers, got {value}"))?;
+    }
+    if parts.next().is_some() {
+        return Err(format!("Expected {N} numbers, got {value}"));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_optional_fields() {
+        let manifest = Manifest::parse(
+            "# comment\n\
+             \n\
+             Sprite platform1 sprites/platform1.png collider:30,35,190,40 tags:platform\n\
+             Sprite char1-idle sprites/char1-idle.png pivot:100,350 tags:player,spawn\n\
+             Sound jump samples/jump.wav\n",
+        )
+        .expect("valid manifest");
+        let [platform, idle, jump] = manifest.entries.as_slice() else {
+            panic!("expected three entries, got {:?}", manifest.entries);
+        };
+        assert_eq!(
+            platform.collider,
+            Some(Collider::from([30.0, 35.0, 190.0, 40.0]))
+        );
+        assert_eq!(platform.pivot, None);
+        assert_eq!(idle.pivot, Some(Vec2::new(100.0, 350.0)));
+        assert!(idle.has_tag("player") && idle.has_tag("spawn"));
+        assert_eq!(jump.kind, AssetKind::Sound);
+        assert_eq!(jump.path, "samples/jump.wav");
+        assert_eq!((jump.pivot, &jump.collider), (None, &None));
+        assert!(jump.tags.is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        for (contents, error) in [
+            ("Music a a.ogg", "manifest.txt:1: Unknown asset kind Music"),
+            ("Sprite", "manifest.txt:1: Asset without an id"),
+            ("Sprite a", "manifest.txt:1: Asset without a path"),
+            (
+                "Sprite a a.png pivot",
+                "manifest.txt:1: Expected key:value, got pivot",
+            ),
+            (
+                "Sprite a a.png pivot:1",
+                "manifest.txt:1: Expected 2 numbers, got 1",
+            ),
+            (
+                "Sprite a a.png pivot:1,2,3",
+                "manifest.txt:1: Expected 2 numbers, got 1,2,3",
+            ),
+            (
+                "Sprite a a.png size:1,2",
+                "manifest.txt:1: Unknown asset field size",
+            ),
+            (
+                "Sprite a a.png\nSprite a b.png",
+                "manifest.txt:2: Duplicate asset id a",
+            ),
+        ] {
+            assert_eq!(Manifest::parse(contents).err().as_deref(), Some(error));
+        }
+        // The same id is fine for different kinds.
+        assert!(Manifest::parse("Sprite a a.png\nSound a a.wav").is_ok());
+    }
+
+    #[test]
+    fn parses_the_shipped_manifest() {
+        let manifest = Manifest::parse(include_str!("../assets/manifest.txt"))
+            .expect("assets/manifest.txt is valid");
+        assert!(manifest.entries.iter().any(|entry| entry.has_tag("spawn")));
+        for entry in &manifest.entries {
+            let path = std::path::Path::new("assets").join(&entry.path);
+            assert!(path.exists(), "{} is missing", path.display());
+        }
+    }
+}
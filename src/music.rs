@@ -0,0 +1,103 @@
+use macroquad::audio::{play_sound, set_sound_volume, stop_sound, PlaySoundParams, Sound};
+
+use crate::common::narrow;
+
+// Seconds for a full crossfade between two tracks.
+const FADE_TIME: f64 = 2.0;
+// Music volume while ducked, for example behind the menu.
+const DUCK_VOLUME: f32 = 0.3;
+// Seconds to duck or recover.
+const DUCK_TIME: f64 = 0.3;
+
+// Looping background tracks. Starting a track fades it in while the old
+// one fades out, so several tracks can be audible for a moment.
+#[derive(Debug, Default)]
+pub struct Music {
+    current: Option<Track>,
+    fading: Vec<Track>,
+    ducked: bool,
+    duck: f32,
+}
+
+#[derive(Debug, Clone)]
+struct Track {
+    id: String,
+    sound: Sound,
+    // 0 is silent, 1 is full volume.
+    fade: f32,
+}
+
+impl Music {
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_ref().map(|track| track.id.as_str())
+    }
+
+    pub fn fading(&self) -> impl Iterator<Item = &str> {
+        self.fading.iter().map(|track| track.id.as_str())
+    }
+
+    pub fn play(&mut self, id: &str, sound: Sound) {
+        if self.current() == Some(id) {
+            return;
+        }
+        if let Some(current) = self.current.take() {
+            self.fading.push(current);
+        }
+        // A track that is still fading out comes back from where it is.
+        if let Some(index) = self.fading.iter().position(|track| track.id == id) {
+            self.current = Some(self.fading.remove(index));
+            return;
+        }
+        play_sound(
+            sound,
+            PlaySoundParams {
+                looped: true,
+                volume: 0.0,
+            },
+        );
+        self.current = Some(Track {
+            id: id.to_owned(),
+            sound,
+            fade: 0.0,
+        });
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(current) = self.current.take() {
+            self.fading.push(current);
+        }
+    }
+
+    pub const fn set_ducked(&mut self, ducked: bool) {
+        self.ducked = ducked;
+    }
+
+    // `gain` is the music bus volume from the mixer.
+    pub fn update(&mut self, delta: f64, gain: f32) {
+        let fade_step = narrow(delta / FADE_TIME);
+        let duck_step = narrow(delta / DUCK_TIME);
+        self.duck = if self.ducked {
+            (self.duck + duck_step).min(1.0)
+        } else {
+            (self.duck - duck_step).max(0.0)
+        };
+        let gain = gain * self.duck.mul_add(DUCK_VOLUME - 1.0, 1.0);
+
+        if let Some(current) = &mut self.current {
+            current.fade = (current.fade + fade_step).min(1.0);
+            set_sound_volume(current.sound, current.fade * gain);
+        }
+        for track in &mut self.fading {
+            track.fade = (track.fade - fade_step).max(0.0);
+            set_sound_volume(track.sound, track.fade * gain);
+        }
+        self.fading.retain(|track| {
+            if track.fade <= 0.0 {
+                stop_sound(track.sound);
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
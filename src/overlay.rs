@@ -0,0 +1,240 @@
+use std::collections::BTreeSet;
+use std::fmt::{self, Display};
+
+use macroquad::prelude::*;
+
+// Relative to the data folder, see `storage`.
+pub const OVERLAY_PATH: &str = "overlay.txt";
+
+// A subsystem's part of the debug overlay. Text goes down the left edge of
+// the screen, `draw_world` draws under the gameplay `camera`.
+pub trait DebugPanel {
+    fn lines(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn draw_world(&self, _camera: &Camera2D) {}
+}
+
+impl<T: DebugPanel + ?Sized> DebugPanel for &T {
+    fn lines(&self) -> Vec<String> {
+        (**self).lines()
+    }
+
+    fn draw_world(&self, camera: &Camera2D) {
+        (**self).draw_world(camera);
+    }
+}
+
+// The panels that exist right now, subsystems add theirs with `register`.
+// Built again every frame, so a panel can borrow what it shows.
+pub struct Panels<'a> {
+    panels: Vec<(&'static str, Box<dyn DebugPanel + 'a>)>,
+}
+
+impl<'a> Panels<'a> {
+    pub fn new() -> Self {
+        Self { panels: Vec::new() }
+    }
+
+    pub fn register(&mut self, name: &'static str, panel: impl DebugPanel + 'a) {
+        self.panels.push((name, Box::new(panel)));
+    }
+
+    // In the order they were registered.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.panels.iter().map(|(name, _)| *name).collect()
+    }
+}
+
+// Frames per second, the one panel that belongs to no subsystem.
+pub struct Fps;
+
+impl DebugPanel for Fps {
+    fn lines(&self) -> Vec<String> {
+        vec![format!("FPS: {}", get_fps())]
+    }
+}
+
+// Which panels are drawn, by name. F3 shows and hides all of them in every
+// state, F4 picks a panel and F2 turns it on or off. Panels that aren't
+// registered in the current state stay enabled for when they are.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Overlay {
+    pub visible: bool,
+    enabled: BTreeSet<String>,
+    selected: Option<&'static str>,
+}
+
+impl Overlay {
+    pub fn new() -> Self {
+        Self {
+            visible: true,
+            enabled: ["fps", "time", "player"]
+                .into_iter()
+                .map(str::to_owned)
+                .collect(),
+            selected: None,
+        }
+    }
+
+    pub fn shows(&self, name: &str) -> bool {
+        self.visible && self.enabled.contains(name)
+    }
+
+    // The panel after the selected one in `names`, the first one to start.
+    pub fn select_next(&mut self, names: &[&'static str]) {
+        let next = self
+            .selected
+            .and_then(|selected| names.iter().position(|name| *name == selected))
+            .map_or(0, |index| index + 1);
+        self.selected = names.get(next).or_else(|| names.first()).copied();
+    }
+
+    pub fn toggle_selected(&mut self) {
+        if let Some(name) = self.selected {
+            if !self.enabled.remove(name) {
+                self.enabled.insert(name.to_owned());
+            }
+        }
+    }
+
+    // `Visible true` and `Panels fps time` lines, anything missing keeps the
+    // defaults.
+    pub fn parse(contents: &str) -> Self {
+        let mut overlay = Self::new();
+        for line in contents.lines() {
+            let (token, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match token {
+                "Visible" => match rest.trim().parse() {
+                    Ok(visible) => overlay.visible = visible,
+                    Err(_) => eprintln!("ERROR: {OVERLAY_PATH}: Bad Visible {rest}"),
+                },
+                "Panels" => {
+                    overlay.enabled = rest.split_ascii_whitespace().map(str::to_owned).collect();
+                }
+                _ => (),
+            }
+        }
+        overlay
+    }
+
+    // World panels under `camera`, then the text ones and the panel list in
+    // the top right corner, with the selected one marked. `hint` tells which
+    // keys change it.
+    pub fn draw(&self, panels: &Panels, camera: &Camera2D, hint: &str) {
+        if !self.visible {
+            return;
+        }
+        let shown = || panels.panels.iter().filter(|(name, _)| self.shows(name));
+        set_camera(camera);
+        for (_, panel) in shown() {
+            panel.draw_world(camera);
+        }
+        set_default_camera();
+        let mut text = PanelText::new();
+        for (_, panel) in shown() {
+            for line in panel.lines() {
+                text.line(&line);
+            }
+        }
+
+        let font_size = 18.0;
+        let x = screen_width() - 170.0;
+        let mut y = 20.0;
+        draw_text(hint, x, y, font_size, GRAY);
+        for name in panels.names() {
+            y += font_size;
+            let mark = if self.enabled.contains(name) {
+                'x'
+            } else {
+                ' '
+            };
+            let color = if self.selected == Some(name) {
+                YELLOW
+            } else {
+                WHITE
+            };
+            draw_text(&format!("[{mark}] {name}"), x, y, font_size, color);
+        }
+    }
+}
+
+impl Default for Overlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for Overlay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Visible {}", self.visible)?;
+        let names: Vec<&str> = self.enabled.iter().map(String::as_str).collect();
+        writeln!(f, "Panels {}", names.join(" "))
+    }
+}
+
+// Text panels stack their lines down the left edge.
+struct PanelText {
+    y: f32,
+}
+
+impl PanelText {
+    const FONT_SIZE: f32 = 18.0;
+
+    const fn new() -> Self {
+        Self { y: 10.0 }
+    }
+
+    fn line(&mut self, text: &str) {
+        draw_text(
+            text,
+            10.0,
+            self.y,
+            Self::FONT_SIZE,
+            color_u8!(255, 255, 255, 255),
+        );
+        self.y += Self::FONT_SIZE + 2.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let mut overlay = Overlay::new();
+        overlay.visible = false;
+        overlay.select_next(&["fps", "collision"]);
+        overlay.select_next(&["fps", "collision"]);
+        overlay.toggle_selected();
+        let parsed = Overlay::parse(&overlay.to_string());
+        assert_eq!(
+            parsed,
+            Overlay {
+                selected: None,
+                ..overlay
+            }
+        );
+    }
+
+    #[test]
+    fn selection_wraps_around() {
+        let names = ["fps", "time", "audio"];
+        let mut overlay = Overlay::new();
+        overlay.select_next(&names);
+        assert_eq!(overlay.selected, Some("fps"));
+        overlay.select_next(&names);
+        overlay.select_next(&names);
+        overlay.toggle_selected();
+        assert!(overlay.enabled.contains("audio"));
+        overlay.select_next(&names);
+        assert_eq!(overlay.selected, Some("fps"));
+        overlay.toggle_selected();
+        assert!(!overlay.shows("fps"));
+        // A panel missing from this state starts the selection over.
+        overlay.select_next(&["camera", "mouse"]);
+        assert_eq!(overlay.selected, Some("camera"));
+    }
+}
@@ -2,11 +2,13 @@ use std::fmt::Display;
 
 use macroquad::math::Vec2;
 
+use crate::ability::{Abilities, Ability};
 use crate::collider::Collider;
-use crate::common::Direction;
-use crate::sprite::Sprites;
+use crate::common::{narrow, Direction};
+use crate::overlay::{DebugPanel, Panels};
+use crate::sprite::{Animation, Clip, Sprites};
 use crate::static_layers::{StaticEntity, StaticLayers};
-use crate::tween::{Tween, TWEEN_PERIOD};
+use crate::tween::Tween;
 
 #[derive(PartialEq, Debug, Copy, Clone)]
 enum PlayerState {
@@ -14,84 +16,224 @@ enum PlayerState {
     Running,
     Jumping,
     Falling,
+    Landing,
+    Spawning,
+}
+
+impl PlayerState {
+    const fn clip(self) -> &'static str {
+        match self {
+            Self::Idle => "idle",
+            Self::Running => "run",
+            Self::Jumping => "jump",
+            Self::Falling => "fall",
+            Self::Landing => "land",
+            Self::Spawning => "spawn",
+        }
+    }
+
+    // States that play their clip once and then return to Idle.
+    const fn is_one_shot(self) -> bool {
+        matches!(self, Self::Landing | Self::Spawning)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum PlayerEvent {
+    Jumped,
+    // Height fallen from the top of the jump.
+    Landed(f32),
+    Animation(String),
+}
+
+// What the controls hold during one tick, the player finds presses itself
+// by comparing with the previous tick.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct PlayerInput {
+    pub left: bool,
+    pub right: bool,
+    pub jump: bool,
+    pub dash: bool,
 }
 
 pub struct Player {
     state: PlayerState,
     pub pos: Vec2,
+    // Position before the last tick, drawing interpolates from it.
+    previous_pos: Vec2,
+    input: PlayerInput,
     right: bool,
     speed_tween: Tween,
     start_jumping_y: f32,
     jump_tween: Tween,
     pub collider: Collider,
-    pub sprites: Vec<String>,
+    clips: Vec<Clip>,
+    animation: Option<Animation>,
+    // Drained by the world after every update.
+    pub events: Vec<PlayerEvent>,
     skip_collision_entities: Vec<StaticEntity>,
     pub on_ground: bool,
+    // Highest point since leaving the ground, y grows downwards.
+    air_top_y: f32,
+    pub abilities: Abilities,
+    dash_right: bool,
 }
 
 impl Player {
-    pub fn new(pos: Vec2, collider: Collider, sprites: &[String]) -> Self {
-        let sprites = sprites.to_vec();
-        debug_assert!(!sprites.is_empty());
+    pub fn new(pos: Vec2, collider: Collider, clips: &[Clip], abilities: &[Ability]) -> Self {
         let speed_tween = Tween::new(&[
             0.0, 25.0, 38.0, 50.0, 60.0, 70.0, 80.0, 84.0, 87.0, 90.0, 93.0, 96.0, 97.0, 98.0,
             99.0, 100.0,
         ]);
         let jump_tween = Tween::new(&JUMP_WAVEFORM);
-        Self {
-            state: PlayerState::Idle,
+        let mut player = Self {
+            state: PlayerState::Spawning,
             pos,
+            previous_pos: pos,
+            input: PlayerInput::default(),
             right: true,
             speed_tween,
             start_jumping_y: pos.y,
             jump_tween,
             collider,
-            sprites,
+            clips: clips.to_vec(),
+            animation: None,
+            events: Vec::new(),
             skip_collision_entities: Vec::new(),
             on_ground: false,
+            air_top_y: pos.y,
+            abilities: Abilities::new(abilities),
+            dash_right: true,
+        };
+        player.select_clip();
+        player
+    }
+
+    // Clips are named `<character>-<state>`, a missing one falls back to idle.
+    fn select_clip(&mut self) {
+        let find = |suffix: &str| {
+            self.clips
+                .iter()
+                .find(|clip| clip.name.rsplit('-').next() == Some(suffix))
+        };
+        let clip = find(self.state.clip());
+        if clip.is_none() && self.state.is_one_shot() {
+            self.state = PlayerState::Idle;
+        }
+        if let Some(clip) = clip.or_else(|| find(PlayerState::Idle.clip())) {
+            if let Some(animation) = &mut self.animation {
+                animation.play(clip);
+            } else {
+                self.animation = Some(Animation::new(clip.clone()));
+            }
         }
     }
 
-    fn init(&mut self) {}
+    fn apply_input(&mut self, input: PlayerInput) {
+        if input.jump {
+            self.jump();
+        } else {
+            self.jump_stop();
+        }
+        if input.jump && !self.input.jump {
+            self.jump_pressed();
+        }
+        if input.dash && !self.input.dash {
+            self.dash();
+        }
+        if input.left {
+            self.left();
+        } else if input.right {
+            self.right();
+        } else {
+            self.stop();
+        }
+        self.input = input;
+    }
 
-    pub fn jump(&mut self) {
-        if self.jump_tween.stopped && self.state != PlayerState::Falling {
+    fn jump(&mut self) {
+        if self.jump_tween.stopped
+            && self.state != PlayerState::Falling
+            && self.state != PlayerState::Spawning
+            && !self.abilities.is_active(Ability::DoubleJump)
+        {
             self.state = PlayerState::Jumping;
             self.jump_tween.stopped = false;
             self.start_jumping_y = self.pos.y;
+            self.events.push(PlayerEvent::Jumped);
         }
     }
 
-    pub fn jump_stop(&mut self) {
-        if self.state == PlayerState::Jumping {
-            if self.jump_tween.time > JUMP_START_OFFSET as f32 * TWEEN_PERIOD {
-                self.state = PlayerState::Falling;
-                self.jump_tween.set_offset(JUMP_END_OFFSET);
-            }
+    fn jump_stop(&mut self) {
+        if self.state == PlayerState::Jumping
+            && self.jump_tween.time > Tween::offset_time(JUMP_START_OFFSET)
+        {
+            self.state = PlayerState::Falling;
+            self.jump_tween.set_offset(JUMP_END_OFFSET);
         }
     }
 
-    pub fn left(&mut self) {
+    // Called once per press, jumping in the air uses the double jump.
+    fn jump_pressed(&mut self) {
+        if self.on_ground || !self.abilities.trigger(Ability::DoubleJump) {
+            return;
+        }
+        self.state = PlayerState::Jumping;
+        self.jump_tween.reset();
+        self.start_jumping_y = self.pos.y;
+        self.events.push(PlayerEvent::Jumped);
+    }
+
+    fn dash(&mut self) {
+        if self.abilities.trigger(Ability::Dash) {
+            self.dash_right = self.right;
+            self.jump_tween.reset();
+            self.start_jumping_y = self.pos.y;
+        }
+    }
+
+    pub const fn facing_right(&self) -> bool {
+        self.right
+    }
+
+    // Back to the spawn point, abilities stay unlocked.
+    pub fn respawn(&mut self, pos: Vec2) {
+        self.pos = pos;
+        self.previous_pos = pos;
+        self.start_jumping_y = pos.y;
+        self.state = PlayerState::Spawning;
+        self.jump_tween.reset();
+        self.speed_tween.reset();
+        self.skip_collision_entities.clear();
+        self.on_ground = false;
+        self.air_top_y = pos.y;
+        self.select_clip();
+    }
+
+    fn left(&mut self) {
         self.speed_tween.stopped = false;
         self.right = false;
 
-        if self.state != PlayerState::Jumping {
+        if self.state != PlayerState::Jumping && !self.state.is_one_shot() {
             self.state = PlayerState::Running;
         }
     }
 
-    pub fn right(&mut self) {
+    fn right(&mut self) {
         self.speed_tween.stopped = false;
         self.right = true;
 
-        if self.state != PlayerState::Jumping {
+        if self.state != PlayerState::Jumping && !self.state.is_one_shot() {
             self.state = PlayerState::Running;
         }
     }
 
-    pub fn stop(&mut self) {
+    fn stop(&mut self) {
         if self.on_ground {
-            self.state = PlayerState::Idle;
+            if !self.state.is_one_shot() {
+                self.state = PlayerState::Idle;
+            }
             self.jump_tween.reset();
         }
         self.speed_tween.reset();
@@ -111,11 +253,39 @@ impl Player {
         false
     }
 
-    pub fn update(&mut self, static_layers: &StaticLayers, delta: f64) {
+    // One fixed tick.
+    pub fn update(&mut self, static_layers: &StaticLayers, input: PlayerInput, delta: f64) {
+        self.previous_pos = self.pos;
+        self.apply_input(input);
+        self.update_animation(delta);
+        if self.state == PlayerState::Spawning {
+            return;
+        }
         self.on_ground = self.on_ground(static_layers);
+        if self.on_ground {
+            self.abilities.land();
+            self.air_top_y = self.pos.y;
+        } else {
+            self.air_top_y = self.air_top_y.min(self.pos.y);
+        }
+        let was_double_jumping = self.abilities.is_active(Ability::DoubleJump);
+        let was_dashing = self.abilities.is_active(Ability::Dash);
+        self.abilities.update(delta);
+        let dash = self.abilities.value(Ability::Dash);
+        let double_jump = self.abilities.value(Ability::DoubleJump);
+        if (was_double_jumping && double_jump.is_none()) || (was_dashing && dash.is_none()) {
+            self.start_jumping_y = self.pos.y;
+            self.state = PlayerState::Falling;
+        }
         // X
         self.speed_tween.update(delta);
-        let speed_x = if self.right {
+        let speed_x = if let Some(dash) = dash {
+            if self.dash_right {
+                dash * DASH_SPEED
+            } else {
+                dash * -DASH_SPEED
+            }
+        } else if self.right {
             self.speed_tween.value() * 20.0
         } else {
             self.speed_tween.value() * -20.0
@@ -125,18 +295,24 @@ impl Player {
         if self.jump_tween.is_over() {
             self.jump_tween.reset();
         }
-        let jump_offset = if self.state == PlayerState::Jumping {
+        let jump_offset = if dash.is_some() {
+            // Dashing ignores gravity.
+            self.start_jumping_y = self.pos.y;
+            0.0
+        } else if let Some(double_jump) = double_jump {
+            double_jump * -DOUBLE_JUMP_HEIGHT
+        } else if self.state == PlayerState::Jumping {
             self.jump_tween.value() * -5.0
         } else if self.on_ground {
             0.0
         } else {
             self.start_jumping_y = self.pos.y;
             self.state = PlayerState::Falling;
-            2500.0 * delta as f32
+            2500.0 * narrow(delta)
         };
 
         let jump_offset_is_zero = jump_offset.abs() < f32::EPSILON;
-        let speed_offset = speed_x * delta as f32;
+        let speed_offset = speed_x * narrow(delta);
         let speed_offset_is_zero = speed_offset.abs() < f32::EPSILON;
 
         if !jump_offset_is_zero || !speed_offset_is_zero {
@@ -160,7 +336,10 @@ impl Player {
                             self.start_jumping_y = self.pos.y;
                             self.pos -= bounce;
                             if bounce.length() > 0.5 {
-                                println!("play sound");
+                                self.state = PlayerState::Landing;
+                                self.jump_tween.reset();
+                                let height = self.pos.y - self.air_top_y;
+                                self.events.push(PlayerEvent::Landed(height));
                             }
                         }
                         Direction::Top => {
@@ -180,36 +359,82 @@ impl Player {
         }
 
         // Set sprite to falling after some jumping time.
-        if self.jump_tween.time > JUMP_END_OFFSET as f32 * TWEEN_PERIOD {
+        if self.jump_tween.time > Tween::offset_time(JUMP_END_OFFSET) {
             self.state = PlayerState::Falling;
         }
+        self.select_clip();
     }
 
-    pub fn draw(&self, sprites: &Sprites) {
-        match (self.state, self.right) {
-            (PlayerState::Idle, true) => sprites.draw(&self.sprites[0], self.pos),
-            (PlayerState::Idle, false) => sprites.draw_flipped(&self.sprites[0], self.pos),
-            (PlayerState::Running, true) => sprites.draw(&self.sprites[0], self.pos),
-            (PlayerState::Running, false) => sprites.draw_flipped(&self.sprites[0], self.pos),
-            (PlayerState::Jumping, true) => sprites.draw(&self.sprites[1], self.pos),
-            (PlayerState::Jumping, false) => sprites.draw_flipped(&self.sprites[1], self.pos),
-            (PlayerState::Falling, true) => sprites.draw(&self.sprites[2], self.pos),
-            (PlayerState::Falling, false) => sprites.draw_flipped(&self.sprites[2], self.pos),
+    fn update_animation(&mut self, delta: f64) {
+        if let Some(animation) = &mut self.animation {
+            for event in animation.update(delta) {
+                self.events.push(PlayerEvent::Animation(event));
+            }
+            if self.state.is_one_shot() && animation.is_finished() {
+                self.state = PlayerState::Idle;
+            }
+        }
+        self.select_clip();
+    }
+
+    // Where to draw, `alpha` of the way from the previous tick to the last one.
+    pub fn interpolated_pos(&self, alpha: f32) -> Vec2 {
+        self.previous_pos.lerp(self.pos, alpha)
+    }
+
+    pub fn draw(&self, sprites: &Sprites, alpha: f32) {
+        if let Some(frame) = self.animation.as_ref().and_then(Animation::frame) {
+            sprites.draw_frame(frame, self.interpolated_pos(alpha), !self.right);
+        }
+    }
+
+    pub fn register_panels<'a>(&'a self, panels: &mut Panels<'a>) {
+        panels.register("player", self);
+        panels.register("tweens", TweenPanel(self));
+    }
+}
+
+impl DebugPanel for Player {
+    fn lines(&self) -> Vec<String> {
+        vec![self.to_string()]
+    }
+}
+
+// The movement curves, one line each.
+struct TweenPanel<'a>(&'a Player);
+
+impl DebugPanel for TweenPanel<'_> {
+    fn lines(&self) -> Vec<String> {
+        let player = self.0;
+        let mut lines = vec![
+            format!("speed: {}", player.speed_tween),
+            format!("jump: {}", player.jump_tween),
+        ];
+        for ability in Ability::ALL {
+            if let Some(state) = player.abilities.get(ability) {
+                lines.push(format!("{}: {}", ability.name(), state.tween));
+            }
         }
+        lines
     }
 }
 
 impl Display for Player {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!(
-            "Player {:?}, jump_tween: {}",
-            self.state, self.jump_tween
+            "Player {:?}, clip: {}, jump_tween: {}, {}",
+            self.state,
+            self.animation.as_ref().map_or("none", Animation::name),
+            self.jump_tween,
+            self.abilities
         ))
     }
 }
 
-const JUMP_START_OFFSET: usize = 10;
-const JUMP_END_OFFSET: usize = 54;
+const DOUBLE_JUMP_HEIGHT: f32 = 3.0;
+const DASH_SPEED: f32 = 40.0;
+const JUMP_START_OFFSET: u16 = 10;
+const JUMP_END_OFFSET: u16 = 54;
 #[allow(clippy::excessive_precision, clippy::unreadable_literal)]
 const JUMP_WAVEFORM: [f32; 60] = [
     1.0,
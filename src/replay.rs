@@ -0,0 +1,284 @@
+use std::fmt::{self, Display};
+
+use crate::ability::{Abilities, Ability};
+use crate::player::PlayerInput;
+
+// Relative to the data folder, see `storage`.
+pub const REPLAY_PATH: &str = "replay.txt";
+
+// Everything a run depends on besides the level, the input of every fixed
+// tick starting from the spawn. The level is only checked by its hash, the
+// run plays on whatever is loaded. Saved as
+//
+// Replay ./data/level0.txt
+// LevelHash 6c62272e07bb0142
+// Seed 1234
+// TickRate 60
+// Abilities { dash }
+// Input 120 -
+// Input 15 RJ
+//
+// where every Input line repeats the held controls for a number of ticks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub level: String,
+    // `level_hash` of the level when it was recorded.
+    pub level_hash: u64,
+    pub seed: u64,
+    pub tick_rate: f64,
+    // Unlocked when the run started.
+    pub abilities: Vec<Ability>,
+    pub inputs: Vec<PlayerInput>,
+}
+
+impl Replay {
+    pub fn new(
+        level: &str,
+        level_hash: u64,
+        seed: u64,
+        tick_rate: f64,
+        abilities: &[Ability],
+    ) -> Self {
+        Self {
+            level: level.to_owned(),
+            level_hash,
+            seed,
+            tick_rate,
+            abilities: abilities.to_vec(),
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut replay = Self::new("", 0, 0, 0.0, &[]);
+        let mut level_hash = None;
+        for (number, line) in contents.lines().enumerate() {
+            let at_line = |why: &str| format!("{}:{}: {}", REPLAY_PATH, number + 1, why);
+            let (token, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match token {
+                "Replay" => rest.trim().clone_into(&mut replay.level),
+                "LevelHash" => {
+                    level_hash = Some(
+                        u64::from_str_radix(rest.trim(), 16)
+                            .map_err(|_| at_line("Could not parse the level hash"))?,
+                    );
+                }
+                "Seed" => {
+                    replay.seed = rest
+                        .trim()
+                        .parse()
+                        .map_err(|_| at_line("Could not parse the seed"))?;
+                }
+                "TickRate" => {
+                    replay.tick_rate = rest
+                        .trim()
+                        .parse()
+                        .map_err(|_| at_line("Could not parse the tick rate"))?;
+                }
+                "Abilities" => replay.abilities = Abilities::parse_list(rest),
+                "Input" => {
+                    let (count, flags) = rest.split_once(' ').unwrap_or((rest, "-"));
+                    let count: usize = count
+                        .parse()
+                        .map_err(|_| at_line("Could not parse the tick count"))?;
+                    let input =
+                        parse_input(flags.trim()).ok_or_else(|| at_line("Unknown input"))?;
+                    replay.inputs.resize(replay.inputs.len() + count, input);
+                }
+                "" => (),
+                _ => return Err(at_line(&format!("Unknown token {token}"))),
+            }
+        }
+        if replay.level.is_empty() || replay.tick_rate < 1.0 {
+            return Err(format!(
+                "{REPLAY_PATH}: Missing the Replay or TickRate line"
+            ));
+        }
+        replay.level_hash = level_hash.ok_or_else(|| {
+            format!(
+                "{REPLAY_PATH}: Missing the LevelHash line, it was recorded by an older version"
+            )
+        })?;
+        Ok(replay)
+    }
+}
+
+impl Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Replay {}", self.level)?;
+        writeln!(f, "LevelHash {:016x}", self.level_hash)?;
+        writeln!(f, "Seed {}", self.seed)?;
+        writeln!(f, "TickRate {}", self.tick_rate)?;
+        writeln!(f, "{}", Abilities::new(&self.abilities))?;
+        let mut inputs = self.inputs.iter().peekable();
+        while let Some(input) = inputs.next() {
+            let mut count = 1;
+            while inputs.next_if_eq(&input).is_some() {
+                count += 1;
+            }
+            writeln!(f, "Input {} {}", count, input_flags(*input))?;
+        }
+        Ok(())
+    }
+}
+
+// A run being played back, one input per tick.
+#[derive(Debug, Clone)]
+pub struct Playback {
+    pub replay: Replay,
+    tick: usize,
+    // Tick rate to go back to once the replay is over.
+    pub previous_tick_rate: f64,
+}
+
+impl Playback {
+    pub const fn new(replay: Replay, previous_tick_rate: f64) -> Self {
+        Self {
+            replay,
+            tick: 0,
+            previous_tick_rate,
+        }
+    }
+
+    // None once every recorded tick has been played.
+    pub fn next_input(&mut self) -> Option<PlayerInput> {
+        let input = self.replay.inputs.get(self.tick).copied();
+        self.tick += 1;
+        input
+    }
+}
+
+// FNV-1a of the saved level, stable across builds unlike the std hashers.
+pub fn level_hash(level: &str) -> u64 {
+    level.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// L left, R right, J jump, D dash, - nothing held.
+fn input_flags(input: PlayerInput) -> String {
+    let flags: String = [
+        (input.left, 'L'),
+        (input.right, 'R'),
+        (input.jump, 'J'),
+        (input.dash, 'D'),
+    ]
+    .iter()
+    .filter(|(held, _)| *held)
+    .map(|(_, flag)| flag)
+    .collect();
+    if flags.is_empty() {
+        "-".to_owned()
+    } else {
+        flags
+    }
+}
+
+fn parse_input(flags: &str) -> Option<PlayerInput> {
+    let mut input = PlayerInput::default();
+    for flag in flags.chars() {
+        match flag {
+            'L' => input.left = true,
+            'R' => input.right = true,
+            'J' => input.jump = true,
+            'D' => input.dash = true,
+            '-' => (),
+            _ => return None,
+        }
+    }
+    Some(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+    use crate::simulation::Simulation;
+
+    const LEVEL: &str = include_str!("../data/level0.txt");
+
+    const IDLE: PlayerInput = PlayerInput {
+        left: false,
+        right: false,
+        jump: false,
+        dash: false,
+    };
+    const RIGHT: PlayerInput = PlayerInput {
+        right: true,
+        ..IDLE
+    };
+    const RUNNING_JUMP: PlayerInput = PlayerInput {
+        right: true,
+        jump: true,
+        ..IDLE
+    };
+    const DASH_LEFT: PlayerInput = PlayerInput {
+        left: true,
+        dash: true,
+        ..IDLE
+    };
+
+    fn recorded() -> Replay {
+        let mut replay = Replay::new(
+            "./data/level0.txt",
+            level_hash(LEVEL),
+            1234,
+            60.0,
+            &[Ability::Dash],
+        );
+        for (input, ticks) in [(IDLE, 30), (RIGHT, 40), (RUNNING_JUMP, 20), (DASH_LEFT, 1)] {
+            replay.inputs.extend(vec![input; ticks]);
+        }
+        replay
+    }
+
+    #[test]
+    fn round_trips() {
+        let replay = recorded();
+        let contents = replay.to_string();
+        for line in ["Input 30 -", "Input 40 R", "Input 20 RJ", "Input 1 LD"] {
+            assert!(contents.lines().any(|other| other == line), "{contents}");
+        }
+        assert_eq!(Replay::parse(&contents), Ok(replay));
+    }
+
+    #[test]
+    fn rejects_bad_replays() {
+        let contents = recorded().to_string();
+        let without_hash: Vec<&str> = contents
+            .lines()
+            .filter(|line| !line.starts_with("LevelHash"))
+            .collect();
+        assert!(Replay::parse(&without_hash.join("\n")).is_err());
+        assert!(Replay::parse(&format!("{contents}Input 3 RX\n")).is_err());
+        assert!(Replay::parse(&format!("{contents}Input many R\n")).is_err());
+    }
+
+    #[test]
+    fn plays_back_every_tick_once() {
+        let replay = recorded();
+        let mut playback = Playback::new(replay.clone(), 30.0);
+        let played: Vec<PlayerInput> = std::iter::from_fn(|| playback.next_input()).collect();
+        assert_eq!(played, replay.inputs);
+        assert_eq!(playback.next_input(), None);
+    }
+
+    #[test]
+    fn saved_replay_runs_the_same() {
+        let level = Level::parse(LEVEL, "level0.txt").expect("the shipped level should parse");
+        let replay = recorded();
+        let delta = 1.0 / replay.tick_rate;
+        let run = |inputs: &[PlayerInput], abilities: &[Ability]| {
+            Simulation::new(&level, &[], abilities)
+                .expect("level0 should have a spawn")
+                .run(inputs, delta)
+        };
+        let original = run(&replay.inputs, &replay.abilities);
+        let loaded = Replay::parse(&replay.to_string()).expect("a saved replay should parse");
+        let mut playback = Playback::new(loaded, replay.tick_rate);
+        let inputs: Vec<PlayerInput> = std::iter::from_fn(|| playback.next_input()).collect();
+        let played = run(&inputs, &playback.replay.abilities);
+        assert_eq!(played.positions, original.positions);
+        assert_eq!(played.events, original.events);
+    }
+}
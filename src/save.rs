@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+use macroquad::math::{vec2, Vec2};
+
+use crate::ability::{Abilities, Ability};
+
+// Bumped whenever the format changes, older files are upgraded on load.
+const SAVE_VERSION: u32 = 1;
+pub const SLOTS: usize = 3;
+// The only save before there were slots, it becomes the first slot.
+pub const LEGACY_SAVE_PATH: &str = "save.txt";
+
+// Relative to the data folder, see `storage`.
+pub fn slot_path(slot: usize) -> String {
+    format!("save{}.txt", slot + 1)
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LevelProgress {
+    pub completed: bool,
+    // Ids of the collectibles picked up.
+    pub collected: Vec<String>,
+    // Fastest finish in seconds.
+    pub best_time: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SavedCheckpoint {
+    pub level: String,
+    pub pos: Vec2,
+}
+
+// Progress of one player. A level is unlocked once it has an entry.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SaveGame {
+    pub levels: BTreeMap<String, LevelProgress>,
+    pub abilities: Vec<Ability>,
+    // Where continuing puts the player.
+    pub checkpoint: Option<SavedCheckpoint>,
+}
+
+impl SaveGame {
+    // A fresh save, with the first level unlocked.
+    pub fn new(first_level: &str) -> Self {
+        let mut save = Self::default();
+        save.unlock(first_level);
+        save
+    }
+
+    pub fn unlock(&mut self, level: &str) -> &mut LevelProgress {
+        self.levels.entry(level.to_owned()).or_default()
+    }
+
+    pub fn is_collected(&self, level: &str, id: &str) -> bool {
+        self.levels
+            .get(level)
+            .is_some_and(|progress| progress.collected.iter().any(|other| other == id))
+    }
+
+    // Returns false if it was already collected.
+    pub fn collect(&mut self, level: &str, id: &str) -> bool {
+        if self.is_collected(level, id) {
+            return false;
+        }
+        self.unlock(level).collected.push(id.to_owned());
+        true
+    }
+
+    // Returns true for a new best time.
+    pub fn complete(&mut self, level: &str, time: f64) -> bool {
+        let progress = self.unlock(level);
+        progress.completed = true;
+        let best = progress.best_time.is_none_or(|best| time < best);
+        if best {
+            progress.best_time = Some(time);
+        }
+        best
+    }
+
+    // Files without a `SaveGame` line are the old save.txt with just abilities.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut save = Self::default();
+        let mut version = 0;
+        for (number, line) in contents.lines().enumerate() {
+            let at_line = |why: &str| format!("line {}: {}", number + 1, why);
+            let (token, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match token {
+                "SaveGame" => {
+                    version = rest
+                        .trim()
+                        .parse()
+                        .map_err(|_| at_line("Could not parse the version"))?;
+                    if version > SAVE_VERSION {
+                        return Err(format!(
+                            "Saved by a newer version ({version}), this one reads up to {SAVE_VERSION}"
+                        ));
+                    }
+                }
+                "Abilities" => save.abilities = Abilities::parse_list(rest),
+                "Level" => {
+                    let (name, progress) = parse_level(rest)
+                        .ok_or_else(|| at_line("Could not parse the level progress"))?;
+                    save.levels.insert(name, progress);
+                }
+                "Checkpoint" => {
+                    save.checkpoint = Some(
+                        parse_checkpoint(rest)
+                            .ok_or_else(|| at_line("Could not parse the checkpoint"))?,
+                    );
+                }
+                "" => (),
+                _ => eprintln!("ERROR: {}", at_line(&format!("Unknown save token {token}"))),
+            }
+        }
+        if version != SAVE_VERSION {
+            println!("Upgrading a save from version {version} to {SAVE_VERSION}");
+        }
+        Ok(save)
+    }
+
+    // One line for the menu.
+    pub fn summary(&self) -> String {
+        let completed = self
+            .levels
+            .values()
+            .filter(|progress| progress.completed)
+            .count();
+        let collected: usize = self
+            .levels
+            .values()
+            .map(|progress| progress.collected.len())
+            .sum();
+        format!(
+            "{}/{} levels, {} collected, {} abilities",
+            completed,
+            self.levels.len(),
+            collected,
+            self.abilities.len()
+        )
+    }
+}
+
+// `level0 completed:true best:12.5 collected:gem1,gem2`
+fn parse_level(rest: &str) -> Option<(String, LevelProgress)> {
+    let mut tokens = rest.split_ascii_whitespace();
+    let name = tokens.next()?.to_owned();
+    let mut progress = LevelProgress::default();
+    for pair in tokens {
+        let (key, value) = pair.split_once(':')?;
+        match key {
+            "completed" => progress.completed = value.parse().ok()?,
+            "best" => progress.best_time = Some(value.parse().ok()?),
+            "collected" => {
+                progress.collected = value
+                    .split(',')
+                    .filter(|id| !id.is_empty())
+                    .map(str::to_owned)
+                    .collect();
+            }
+            _ => eprintln!("ERROR: Unknown level progress key {key}"),
+        }
+    }
+    Some((name, progress))
+}
+
+// `level0 x:100 y:-2300`
+fn parse_checkpoint(rest: &str) -> Option<SavedCheckpoint> {
+    let mut tokens = rest.split_ascii_whitespace();
+    let level = tokens.next()?.to_owned();
+    let (mut x, mut y) = (None, None);
+    for pair in tokens {
+        let (key, value) = pair.split_once(':')?;
+        match key {
+            "x" => x = Some(value.parse().ok()?),
+            "y" => y = Some(value.parse().ok()?),
+            _ => return None,
+        }
+    }
+    Some(SavedCheckpoint {
+        level,
+        pos: vec2(x?, y?),
+    })
+}
+
+impl Display for SaveGame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "SaveGame {SAVE_VERSION}")?;
+        writeln!(f, "{}", Abilities::new(&self.abilities))?;
+        for (name, progress) in &self.levels {
+            write!(f, "Level {} completed:{}", name, progress.completed)?;
+            if let Some(best) = progress.best_time {
+                write!(f, " best:{best}")?;
+            }
+            if !progress.collected.is_empty() {
+                write!(f, " collected:{}", progress.collected.join(","))?;
+            }
+            writeln!(f)?;
+        }
+        if let Some(SavedCheckpoint { level, pos }) = &self.checkpoint {
+            writeln!(f, "Checkpoint {} x:{} y:{}", level, pos.x, pos.y)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let mut save = SaveGame::new("level0");
+        save.abilities = vec![Ability::Dash, Ability::DoubleJump];
+        save.collect("level0", "gem1");
+        save.collect("level0", "gem2");
+        save.complete("level0", 12.5);
+        save.unlock("level1");
+        save.checkpoint = Some(SavedCheckpoint {
+            level: "level1".to_owned(),
+            pos: vec2(100.0, -2300.5),
+        });
+        let parsed = SaveGame::parse(&save.to_string());
+        assert_eq!(parsed, Ok(save));
+    }
+
+    #[test]
+    fn upgrades_the_legacy_save() {
+        let save = SaveGame::parse("Abilities { double-jump, dash }\n");
+        assert_eq!(
+            save,
+            Ok(SaveGame {
+                abilities: vec![Ability::DoubleJump, Ability::Dash],
+                ..SaveGame::default()
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        assert!(SaveGame::parse("SaveGame 2\nAbilities {  }\n").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        let bad_level = SaveGame::parse("SaveGame 1\nLevel level0 completed:maybe\n");
+        assert!(bad_level.is_err_and(|why| why.starts_with("line 2:")));
+        for checkpoint in ["level0 x:1", "level0 x:1 y:up", "level0 x:1 y:2 z:3", ""] {
+            let contents = format!("SaveGame 1\nCheckpoint {checkpoint}\n");
+            assert!(SaveGame::parse(&contents).is_err(), "{contents}");
+        }
+    }
+
+    #[test]
+    fn keeps_the_best_time() {
+        let mut save = SaveGame::new("level0");
+        assert!(save.complete("level0", 20.0));
+        assert!(!save.complete("level0", 25.0));
+        assert!(save.complete("level0", 15.0));
+        let best = save
+            .levels
+            .get("level0")
+            .and_then(|progress| progress.best_time);
+        assert_eq!(best, Some(15.0));
+    }
+}
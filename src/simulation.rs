@@ -0,0 +1,380 @@
+use macroquad::camera::Camera2D;
+use macroquad::math::Vec2;
+
+use crate::ability::Ability;
+use crate::checkpoint::Checkpoint;
+use crate::level::Level;
+use crate::overlay::{DebugPanel, Panels};
+use crate::player::{Player, PlayerEvent, PlayerInput};
+use crate::sprite::Clip;
+use crate::static_layers::StaticLayers;
+use crate::trigger::{Trigger, TriggerAction};
+
+// The entity the player starts on, its collider becomes the player's. It
+// stays in the simulation's copy of the level as a marker without a collider.
+pub const SPAWN: &str = "char1-spawn";
+// How far below the lowest thing in the level the player can fall.
+const KILL_MARGIN: f32 = 2000.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimulationEvent {
+    Player(PlayerEvent),
+    // A trigger fired, at the center of its area.
+    Trigger(TriggerAction, Vec2),
+    // Fell out of the level here and respawned.
+    Died(Vec2),
+    // A checkpoint was touched, the player respawns here from now on.
+    Checkpoint(Vec2),
+}
+
+// One run of a level: the player, what it collides with and what it can
+// touch, on a copy of the level. Needs no window, textures or input devices,
+// so tests can drive it with scripted input.
+pub struct Simulation {
+    pub static_layers: StaticLayers,
+    pub triggers: Vec<Trigger>,
+    pub checkpoints: Vec<Checkpoint>,
+    pub player: Player,
+    // Where the player respawns, the spawn marker or the last checkpoint.
+    pub spawn_pos: Vec2,
+    // Falling below this kills the player.
+    kill_y: f32,
+    pub ticks: usize,
+}
+
+// Where the player was after every tick and what happened on which tick.
+// Only the tests record one, see `Simulation::run`.
+#[cfg(test)]
+#[derive(Debug, Clone, Default)]
+pub struct Trace {
+    pub positions: Vec<Vec2>,
+    pub events: Vec<(usize, SimulationEvent)>,
+}
+
+impl Simulation {
+    pub fn new(level: &Level, clips: &[Clip], abilities: &[Ability]) -> Result<Self, String> {
+        let mut static_layers = level.static_layers.clone();
+        let spawn = static_layers
+            .find(SPAWN)
+            .ok_or_else(|| format!("The level has no {SPAWN}"))?;
+        let collider = spawn
+            .collider
+            .clone()
+            .ok_or_else(|| format!("{SPAWN} has no collider"))?;
+        let spawn_pos = spawn.pos;
+        static_layers.remove_collider(SPAWN);
+        let kill_y = static_layers
+            .lowest_point()
+            .map_or(f32::MAX, |lowest| lowest + KILL_MARGIN);
+        let mut triggers = level.triggers.clone();
+        for trigger in &mut triggers {
+            trigger.fired = false;
+        }
+        let mut checkpoints = level.checkpoints.clone();
+        for checkpoint in &mut checkpoints {
+            checkpoint.set_active(false);
+        }
+        Ok(Self {
+            static_layers,
+            triggers,
+            checkpoints,
+            player: Player::new(spawn_pos, collider, clips, abilities),
+            spawn_pos,
+            kill_y,
+            ticks: 0,
+        })
+    }
+
+    // Respawns at `pos` from now on, a checkpoint there shows as active.
+    pub fn respawn_at(&mut self, pos: Vec2) {
+        self.spawn_pos = pos;
+        self.player.respawn(pos);
+        for checkpoint in &mut self.checkpoints {
+            checkpoint.set_active(checkpoint.spawn_pos() == pos);
+        }
+    }
+
+    // One fixed tick.
+    pub fn tick(&mut self, input: PlayerInput, delta: f64) -> Vec<SimulationEvent> {
+        self.ticks += 1;
+        self.player.update(&self.static_layers, input, delta);
+        let mut events: Vec<SimulationEvent> = self
+            .player
+            .events
+            .drain(..)
+            .map(SimulationEvent::Player)
+            .collect();
+        if self.player.pos.y > self.kill_y {
+            events.push(SimulationEvent::Died(self.player.pos));
+            self.player.respawn(self.spawn_pos);
+        }
+        let player_rect = self.player.collider.rect().offset(self.player.pos);
+        for checkpoint in &mut self.checkpoints {
+            checkpoint.update(delta);
+        }
+        // Touching an older checkpoint again makes it the current one.
+        let touched = self
+            .checkpoints
+            .iter()
+            .position(|checkpoint| !checkpoint.active && checkpoint.overlaps(player_rect));
+        if let Some(index) = touched {
+            for (i, checkpoint) in self.checkpoints.iter_mut().enumerate() {
+                if i == index {
+                    checkpoint.activate();
+                    self.spawn_pos = checkpoint.spawn_pos();
+                } else {
+                    checkpoint.set_active(false);
+                }
+            }
+            events.push(SimulationEvent::Checkpoint(self.spawn_pos));
+        }
+        for trigger in &mut self.triggers {
+            if !trigger.fired && trigger.overlaps(player_rect) {
+                trigger.fired = true;
+                if let TriggerAction::GrantAbility(ability) = trigger.action {
+                    self.player.abilities.grant(ability);
+                }
+                events.push(SimulationEvent::Trigger(
+                    trigger.action.clone(),
+                    trigger.collider.center(),
+                ));
+            }
+        }
+        events
+    }
+
+    // The player's panels and the colliders, with the player drawn `alpha` of
+    // the way to its next position.
+    pub fn register_panels<'a>(&'a self, panels: &mut Panels<'a>, alpha: f32) {
+        self.player.register_panels(panels);
+        panels.register(
+            "collision",
+            CollisionPanel {
+                simulation: self,
+                alpha,
+            },
+        );
+    }
+
+    // A tick for every input, for tests. The game ticks through `tick`.
+    #[cfg(test)]
+    pub fn run(&mut self, inputs: &[PlayerInput], delta: f64) -> Trace {
+        let mut trace = Trace::default();
+        for input in inputs {
+            for event in self.tick(*input, delta) {
+                trace.events.push((self.ticks, event));
+            }
+            trace.positions.push(self.player.pos);
+        }
+        trace
+    }
+}
+
+struct CollisionPanel<'a> {
+    simulation: &'a Simulation,
+    alpha: f32,
+}
+
+impl DebugPanel for CollisionPanel<'_> {
+    fn draw_world(&self, camera: &Camera2D) {
+        let simulation = self.simulation;
+        simulation.static_layers.draw_colliders(camera);
+        for trigger in &simulation.triggers {
+            trigger.debug();
+        }
+        for checkpoint in &simulation.checkpoints {
+            checkpoint.debug();
+        }
+        let player = &simulation.player;
+        player.collider.draw(player.interpolated_pos(self.alpha));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::math::vec2;
+
+    use super::*;
+    use crate::static_layers::StaticEntity;
+
+    const TICK: f64 = 1.0 / 60.0;
+
+    const IDLE: PlayerInput = PlayerInput {
+        left: false,
+        right: false,
+        jump: false,
+        dash: false,
+    };
+    const RIGHT: PlayerInput = PlayerInput {
+        right: true,
+        ..IDLE
+    };
+    const RUNNING_JUMP: PlayerInput = PlayerInput {
+        right: true,
+        jump: true,
+        ..IDLE
+    };
+
+    fn level0() -> Simulation {
+        let level = Level::parse(include_str!("../data/level0.txt"), "level0.txt")
+            .expect("the shipped level should parse");
+        Simulation::new(&level, &[], &[]).expect("level0 should have a spawn")
+    }
+
+    fn inputs(input: PlayerInput, ticks: usize) -> Vec<PlayerInput> {
+        vec![input; ticks]
+    }
+
+    // The tick from which the player stays at the last position.
+    fn settled(trace: &Trace) -> Option<usize> {
+        let last = trace.positions.last()?;
+        let moving = trace.positions.iter().rposition(|pos| pos != last);
+        Some(moving.map_or(0, |tick| tick + 1))
+    }
+
+    #[test]
+    fn lands_on_the_first_platform() {
+        let mut simulation = level0();
+        let start = simulation.player.pos;
+        let trace = simulation.run(&inputs(IDLE, 60), TICK);
+        assert!(settled(&trace).expect("ran some ticks") < 30);
+        assert!(simulation.player.on_ground);
+        let end = simulation.player.pos;
+        assert!((end.x - start.x).abs() < f32::EPSILON);
+        // Standing on the platform below the spawn, its top is at -2303.
+        let feet = end.y + simulation.player.collider.pos.y + simulation.player.collider.height;
+        assert!((-2303.0 - 20.0..=-2303.0).contains(&feet), "feet at {feet}");
+    }
+
+    #[test]
+    fn walks_right() {
+        let mut simulation = level0();
+        simulation.run(&inputs(IDLE, 60), TICK);
+        let start = simulation.player.pos;
+        simulation.run(&inputs(RIGHT, 10), TICK);
+        assert!(simulation.player.facing_right());
+        assert!(simulation.player.pos.x > start.x);
+        assert!((simulation.player.pos.y - start.y).abs() < 1.0);
+    }
+
+    #[test]
+    fn running_jump_reaches_the_dash_platform() {
+        let mut simulation = level0();
+        simulation.run(&inputs(IDLE, 60), TICK);
+        // Standing still cancels the jump on the ground, so jump while running.
+        let mut script = inputs(RUNNING_JUMP, 30);
+        script.extend(inputs(IDLE, 60));
+        let trace = simulation.run(&script, TICK);
+        let jumps = trace
+            .events
+            .iter()
+            .filter(|(_, event)| event == &SimulationEvent::Player(PlayerEvent::Jumped));
+        assert_eq!(jumps.count(), 1);
+        assert!(settled(&trace).expect("ran some ticks") < script.len());
+        assert!(simulation.player.on_ground);
+        // On the platform at x:937, its top is at -2696.
+        let player = &simulation.player;
+        let feet = player.pos.y + player.collider.pos.y + player.collider.height;
+        assert!((-2696.0 - 20.0..=-2696.0).contains(&feet), "feet at {feet}");
+        assert!(trace.events.iter().any(|(_, event)| matches!(
+            event,
+            SimulationEvent::Trigger(TriggerAction::GrantAbility(Ability::Dash), _)
+        )));
+        assert!(player.abilities.has(Ability::Dash));
+    }
+
+    #[test]
+    fn falling_out_of_the_level_respawns() {
+        let level = Level::parse(
+            "StaticLayer 0:\n\
+             StaticEntity { char1-spawn, x:0, y:0, Collider { x:50, y:40, w:100, h:300 } }\n\
+             StaticEntity { platform1, x:5000, y:0, Collider { x:30, y:35, w:190, h:40 } }\n",
+            "test",
+        )
+        .expect("the test level should parse");
+        let mut simulation = Simulation::new(&level, &[], &[]).expect("has a spawn");
+        let trace = simulation.run(&inputs(IDLE, 600), TICK);
+        let died = trace
+            .events
+            .iter()
+            .find(|(_, event)| matches!(event, SimulationEvent::Died(_)));
+        assert!(died.is_some(), "never died in {:?}", trace.events);
+        assert_eq!(simulation.spawn_pos, vec2(0.0, 0.0));
+    }
+
+    #[test]
+    fn respawns_at_the_last_checkpoint() {
+        let level = Level::parse(
+            "StaticLayer 0:\n\
+             StaticEntity { char1-spawn, x:0, y:0, Collider { x:50, y:40, w:100, h:300 } }\n\
+             StaticEntity { platform1, x:5000, y:0, Collider { x:30, y:35, w:190, h:40 } }\n\
+             Checkpoint { x:0, y:600, w:200, h:200 }\n",
+            "test",
+        )
+        .expect("the test level should parse");
+        let mut simulation = Simulation::new(&level, &[], &[]).expect("has a spawn");
+        let trace = simulation.run(&inputs(IDLE, 600), TICK);
+        let checkpoint = trace
+            .events
+            .iter()
+            .position(|(_, event)| event == &SimulationEvent::Checkpoint(vec2(0.0, 600.0)));
+        let died = trace
+            .events
+            .iter()
+            .position(|(_, event)| matches!(event, SimulationEvent::Died(_)));
+        assert!(
+            matches!((checkpoint, died), (Some(checkpoint), Some(died)) if checkpoint < died),
+            "events {:?}",
+            trace.events
+        );
+        assert_eq!(simulation.spawn_pos, vec2(0.0, 600.0));
+        assert!(simulation
+            .checkpoints
+            .first()
+            .is_some_and(|checkpoint| checkpoint.active));
+        // The level itself is left alone.
+        assert!(level
+            .static_layers
+            .find(SPAWN)
+            .and_then(|spawn| spawn.collider.as_ref())
+            .is_some());
+    }
+
+    // What Play changes stays in the simulation, going back to Edit finds the
+    // level as it was.
+    #[test]
+    fn play_leaves_the_level_alone() {
+        let level = Level::parse(
+            "StaticLayer 0:\n\
+             StaticEntity { char1-spawn, x:0, y:0, Collider { x:50, y:40, w:100, h:300 } }\n\
+             Checkpoint { x:0, y:600, w:200, h:200 }\n",
+            "test",
+        )
+        .expect("the test level should parse");
+        let before = level.to_string();
+        let mut simulation = Simulation::new(&level, &[], &[]).expect("has a spawn");
+        simulation.run(&inputs(IDLE, 600), TICK);
+        let platform = StaticEntity::parse("platform1, x:0, y:500").expect("a valid entity");
+        simulation.static_layers.add_entity(0, platform);
+        simulation.respawn_at(vec2(300.0, 0.0));
+        assert_eq!(level.to_string(), before);
+        let again = Simulation::new(&level, &[], &[]).expect("has a spawn");
+        assert_eq!(again.spawn_pos, vec2(0.0, 0.0));
+        assert!(again
+            .checkpoints
+            .iter()
+            .all(|checkpoint| !checkpoint.active));
+    }
+
+    #[test]
+    fn runs_are_deterministic() {
+        let mut script = inputs(IDLE, 30);
+        script.extend(inputs(RIGHT, 40));
+        script.extend(inputs(RUNNING_JUMP, 20));
+        script.extend(inputs(IDLE, 60));
+        let first = level0().run(&script, TICK);
+        let second = level0().run(&script, TICK);
+        assert_eq!(first.positions, second.positions);
+        assert_eq!(first.events, second.events);
+    }
+}
@@ -1,130 +1,358 @@
 use std::collections::HashMap;
 
-use macroquad::math::Vec2;
-use macroquad::prelude::{draw_texture_ex, DrawTextureParams};
-use macroquad::texture::{draw_texture, load_texture, Texture2D};
+use macroquad::math::{Rect, Vec2};
+use macroquad::prelude::{draw_texture_ex, DrawTextureParams, ImageFormat};
+use macroquad::texture::{Image, Texture2D};
 use macroquad::ui;
 use macroquad::{color::Color, color_u8};
 
-use crate::SPRITE_PATHS;
+use crate::atlas::{Atlas, PADDING, PAGE_SIZE};
+use crate::diagnostics::Diagnostics;
+use crate::manifest::AssetEntry;
 
 pub struct Sprites {
-    sprites: HashMap<&'static str, Sprite>,
+    by_name: HashMap<String, Sprite>,
+    // Kept around to rebuild the atlas on reload.
+    images: Vec<(AssetEntry, Image)>,
+    clips: HashMap<String, Clip>,
+    // (path, contents) of every clip file.
+    clip_sources: Vec<(String, String)>,
+    pages: Vec<Texture2D>,
+    // Drawn in place of sprites that failed to load or do not exist.
+    placeholder: Option<Sprite>,
+    pub diagnostics: Diagnostics,
 
-    debug: Option<Sprite>,
+    debug: Option<String>,
 }
 
 impl Sprites {
-    pub async fn new() -> Self {
-        let paths = SPRITE_PATHS.split('\n');
-        let mut sprites = HashMap::with_capacity(5);
-        for path in paths {
-            let name = path
-                .split('/')
-                .last()
-                .expect("Path should have sprites/ folder, thus have '/' char")
-                .split('.')
-                .take(1)
-                .last()
-                .expect("Path should include the .png extension");
-            let sprite = Sprite::new(path).await;
-            sprites.insert(name, sprite);
+    pub fn from_loaded(
+        loaded: Vec<(AssetEntry, Result<Image, String>)>,
+        clips: Vec<(AssetEntry, Result<String, String>)>,
+        diagnostics: Diagnostics,
+    ) -> Result<Self, String> {
+        let mut images = Vec::with_capacity(loaded.len());
+        for (entry, image) in loaded {
+            let image = image.unwrap_or_else(|why| {
+                diagnostics.missing("sprite", &entry.id, &why);
+                placeholder_image()
+            });
+            images.push((entry, image));
+        }
+        let mut clip_sources = Vec::with_capacity(clips.len());
+        for (entry, contents) in clips {
+            let contents = contents.unwrap_or_else(|why| {
+                diagnostics.missing("clips", &entry.id, &why);
+                String::new()
+            });
+            clip_sources.push((entry.path, contents));
         }
 
-        Self {
-            sprites,
+        let mut result = Self {
+            by_name: HashMap::with_capacity(images.len()),
+            images,
+            clips: HashMap::new(),
+            clip_sources,
+            pages: Vec::new(),
+            placeholder: None,
+            diagnostics,
             debug: None,
-        }
+        };
+        result.build_atlas();
+        result.build_clips();
+        result.diagnostics.check()?;
+        Ok(result)
     }
 
-    pub fn get_sprite_name_by_id(&self, id: usize) -> Option<&'static str> {
-        for (i, name) in self.sprites.keys().enumerate() {
-            if id == i {
-                return Some(*name);
-            }
+    // Every sprite ends up on one of a few shared textures, so drawing
+    // does not have to switch textures between sprites.
+    fn build_atlas(&mut self) {
+        let mut images: Vec<Image> = self.images.iter().map(|(_, image)| image.clone()).collect();
+        images.push(placeholder_image());
+        let atlas = Atlas::build(&images, PAGE_SIZE, PADDING);
+        for page in self.pages.drain(..) {
+            page.delete();
         }
-        eprintln!("ERROR: No sprite with id {} found", id);
-        None
+        self.pages = atlas.pages.iter().map(Texture2D::from_image).collect();
+        self.by_name.clear();
+        for ((entry, _), placement) in self.images.iter().zip(&atlas.placements) {
+            let texture = *self
+                .pages
+                .get(placement.page)
+                .expect("Atlas placement should point at an existing page");
+            self.by_name
+                .insert(entry.id.clone(), Sprite::new(texture, placement.rect()));
+        }
+        self.placeholder = atlas.placements.last().and_then(|placement| {
+            let texture = *self.pages.get(placement.page)?;
+            Some(Sprite::new(texture, placement.rect()))
+        });
     }
 
-    pub fn get_sprite(&self, name: &str) -> Option<&Sprite> {
-        if let Some(sprite) = self.sprites.get(name) {
+    // Falls back to the placeholder, reporting the name once.
+    fn sprite(&self, name: &str) -> Option<&Sprite> {
+        if let Some(sprite) = self.by_name.get(name) {
             return Some(sprite);
         }
-        eprintln!("ERROR: No sprite with name {} found", name);
-        return None;
+        self.diagnostics
+            .missing("sprite", name, "not in the asset manifest");
+        self.placeholder.as_ref()
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.images.iter().map(|(entry, _)| entry.path.as_str())
+    }
+
+    pub fn clip_paths(&self) -> impl Iterator<Item = &str> {
+        self.clip_sources.iter().map(|(path, _)| path.as_str())
+    }
+
+    pub fn meta(&self, name: &str) -> Option<&AssetEntry> {
+        self.images
+            .iter()
+            .map(|(entry, _)| entry)
+            .find(|entry| entry.id == name)
+    }
+
+    pub fn has_tag(&self, name: &str, tag: &str) -> bool {
+        self.meta(name).is_some_and(|entry| entry.has_tag(tag))
+    }
+
+    // The point of the sprite that lines up with the cursor, the center by default.
+    pub fn pivot(&self, name: &str) -> Option<Vec2> {
+        let size = self.sprite(name)?.size();
+        Some(
+            self.meta(name)
+                .and_then(|entry| entry.pivot)
+                .unwrap_or(size * 0.5),
+        )
+    }
+
+    // Replaces the pixels of an already loaded sprite and repacks the atlas.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload(&mut self, path: &str, bytes: &[u8]) -> Result<(), String> {
+        let image = decode_png(path, bytes)?;
+        let entry = self
+            .images
+            .iter_mut()
+            .find(|(entry, _)| entry.path == path)
+            .ok_or_else(|| format!("{path} is not a known sprite"))?;
+        entry.1 = image;
+        self.build_atlas();
+        // Frame rectangles depend on the sprite size.
+        self.build_clips();
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload_clips(&mut self, path: &str, contents: String) -> Result<(), String> {
+        let source = self
+            .clip_sources
+            .iter_mut()
+            .find(|(clip_path, _)| clip_path == path)
+            .ok_or_else(|| format!("{path} is not a known clip file"))?;
+        source.1 = contents;
+        self.build_clips();
+        Ok(())
+    }
+
+    fn build_clips(&mut self) {
+        let contents: Vec<&str> = self
+            .clip_sources
+            .iter()
+            .map(|(_, contents)| contents.as_str())
+            .collect();
+        let contents = contents.join("\n");
+        self.load_clips(&contents);
+    }
+
+    fn load_clips(&mut self, contents: &str) {
+        let (clips, errors) = parse_clips(contents, |sprite| {
+            self.by_name.get(sprite).map(Sprite::size)
+        });
+        for error in errors {
+            match error {
+                ClipError::Line(why) => eprintln!("ERROR: {why}"),
+                ClipError::MissingSprite(sprite) => {
+                    self.diagnostics
+                        .missing("sprite", &sprite, "used by an animation frame");
+                }
+            }
+        }
+        self.clips = clips;
+    }
+
+    pub fn get_clips(&self, prefix: &str) -> Vec<Clip> {
+        self.clips
+            .values()
+            .filter(|clip| clip.name.starts_with(prefix))
+            .cloned()
+            .collect()
     }
 
     pub fn draw(&self, sprite: &str, pos: Vec2) {
-        if let Some(sprite) = self.sprites.get(sprite) {
+        if let Some(sprite) = self.sprite(sprite) {
             sprite.draw(pos);
-        } else {
-            eprintln!("ERROR: Tried to draw a non-existing sprite: {}", sprite);
         }
     }
 
-    pub fn draw_flipped(&self, sprite: &str, pos: Vec2) {
-        if let Some(sprite) = self.sprites.get(sprite) {
-            sprite.draw_flipped(pos);
-        } else {
-            eprintln!("ERROR: Tried to draw a non-existing sprite: {}", sprite);
+    pub fn draw_frame(&self, frame: &Frame, pos: Vec2, flipped: bool) {
+        if let Some(sprite) = self.by_name.get(frame.sprite.as_str()) {
+            sprite.draw_source(pos, frame.source, flipped);
+        } else if let Some(sprite) = self.sprite(&frame.sprite) {
+            sprite.draw(pos);
         }
     }
 
-    pub fn draw_at_center(&self, sprite: &str, pos: Vec2) {
-        if let Some(sprite) = self.sprites.get(sprite) {
-            let offset = sprite.size() * 0.5;
-            sprite.draw(pos - offset);
-        } else {
-            eprintln!("ERROR: Tried to draw a non-existing sprite: {}", sprite);
+    pub fn draw_at_center(&self, name: &str, pos: Vec2) {
+        if let (Some(sprite), Some(pivot)) = (self.sprite(name), self.pivot(name)) {
+            sprite.draw(pos - pivot);
         }
     }
 
-    pub fn ui(&self) -> Option<&'static str> {
-        let mut selection = None;
-        for name in self.sprites.keys() {
-            if ui::root_ui().button(None, *name) {
-                selection = Some(*name);
+    pub fn debug(&mut self) {
+        for name in self.by_name.keys() {
+            if ui::root_ui().button(None, name.as_str()) {
+                self.debug = Some(name.clone());
             }
         }
-        selection
+        if let Some(sprite) = self.debug.as_ref().and_then(|name| self.by_name.get(name)) {
+            sprite.draw(Vec2::new(0.0, 0.0));
+        }
+        ui::root_ui().label(None, &format!("Atlas pages: {}", self.pages.len()));
+        self.diagnostics.ui();
     }
+}
 
-    pub fn debug(&mut self) {
-        for (name, sprite) in &self.sprites {
-            if ui::root_ui().button(None, *name) {
-                self.debug = Some(sprite.clone());
+// Decoding panics on broken files, which are common while an editor is saving,
+// so they are checked first.
+pub fn decode_png(path: &str, bytes: &[u8]) -> Result<Image, String> {
+    validate_png(bytes).map_err(|why| format!("{path} is not a valid png: {why}"))?;
+    Ok(Image::from_file_with_format(bytes, Some(ImageFormat::Png)))
+}
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// Every chunk is complete and matches its checksum, the header comes first
+// and describes an image the decoder supports, and the file ends after image
+// data.
+fn validate_png(bytes: &[u8]) -> Result<(), &'static str> {
+    let mut rest = bytes
+        .strip_prefix(&PNG_SIGNATURE)
+        .ok_or("missing the png signature")?;
+    let mut first = true;
+    let mut has_data = false;
+    loop {
+        let (length, after) = split_u32(rest).ok_or("truncated chunk")?;
+        let length = usize::try_from(length).map_err(|_| "chunk too long")?;
+        let (chunk, after) = after
+            .split_at_checked(length + 4)
+            .ok_or("truncated chunk")?;
+        let (crc, after) = split_u32(after).ok_or("truncated chunk")?;
+        if crc32(chunk) != crc {
+            return Err("chunk checksum mismatch");
+        }
+        let (kind, data) = chunk.split_at_checked(4).ok_or("truncated chunk")?;
+        if first {
+            if kind != b"IHDR" {
+                return Err("the header is not the first chunk");
+            }
+            validate_header(data)?;
+            first = false;
+        }
+        match kind {
+            b"IDAT" => has_data = true,
+            b"IEND" if has_data => return Ok(()),
+            b"IEND" => return Err("no image data"),
+            _ => (),
+        }
+        rest = after;
+    }
+}
+
+fn validate_header(data: &[u8]) -> Result<(), &'static str> {
+    let [w0, w1, w2, w3, h0, h1, h2, h3, depth, color, compression, filter, interlace] =
+        <[u8; 13]>::try_from(data).map_err(|_| "bad header length")?;
+    if u32::from_be_bytes([w0, w1, w2, w3]) == 0 || u32::from_be_bytes([h0, h1, h2, h3]) == 0 {
+        return Err("empty image");
+    }
+    let depth_ok = match color {
+        0 => matches!(depth, 1 | 2 | 4 | 8 | 16),
+        3 => matches!(depth, 1 | 2 | 4 | 8),
+        2 | 4 | 6 => matches!(depth, 8 | 16),
+        _ => return Err("unknown color type"),
+    };
+    if !depth_ok {
+        return Err("bad bit depth for the color type");
+    }
+    if compression != 0 || filter != 0 || interlace > 1 {
+        return Err("unknown compression, filter or interlace method");
+    }
+    Ok(())
+}
+
+fn split_u32(bytes: &[u8]) -> Option<(u32, &[u8])> {
+    let (head, rest) = bytes.split_first_chunk()?;
+    Some((u32::from_be_bytes(*head), rest))
+}
+
+// The CRC-32 png chunks end with, over the chunk type and data.
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, byte| {
+        (0..8).fold(crc ^ u32::from(*byte), |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+// Magenta and black checkerboard, hard to miss in a level.
+fn placeholder_image() -> Image {
+    let (size, square) = (64u16, 16u16);
+    let mut image = Image::gen_image_color(size, size, color_u8!(0, 0, 0, 255));
+    for x in 0..size {
+        for y in 0..size {
+            if (x.div_euclid(square) + y.div_euclid(square)) % 2 == 0 {
+                image.set_pixel(u32::from(x), u32::from(y), color_u8!(255, 0, 255, 255));
             }
         }
-        if let Some(sprite) = &self.debug {
-            sprite.draw(Vec2::new(0.0, 0.0));
-        };
     }
+    image
 }
 
+// A sub-rectangle of an atlas page.
 #[derive(Debug, Clone)]
 pub struct Sprite {
-    path: &'static str,
     texture: Texture2D,
+    source: Rect,
 }
 
 impl Sprite {
-    pub async fn new(path: &'static str) -> Self {
-        let texture = load_texture(path).await.expect("Failed to load texture");
-        Self { path, texture }
+    pub const fn new(texture: Texture2D, source: Rect) -> Self {
+        Self { texture, source }
     }
 
     pub fn size(&self) -> Vec2 {
-        Vec2::new(self.texture.width(), self.texture.height())
+        self.source.size()
     }
 
     pub fn draw(&self, pos: Vec2) {
-        draw_texture(self.texture, pos.x, pos.y, color_u8!(255, 255, 255, 255));
+        self.draw_source(
+            pos,
+            Rect::new(0.0, 0.0, self.source.w, self.source.h),
+            false,
+        );
     }
 
-    pub fn draw_flipped(&self, pos: Vec2) {
+    // `source` is relative to the sprite, not to the atlas page.
+    pub fn draw_source(&self, pos: Vec2, source: Rect, flipped: bool) {
         let params: DrawTextureParams = DrawTextureParams {
-            flip_x: true,
+            dest_size: Some(source.size()),
+            source: Some(source.offset(self.source.point())),
+            flip_x: flipped,
             ..DrawTextureParams::default()
         };
         draw_texture_ex(
@@ -136,3 +364,317 @@ impl Sprite {
         );
     }
 }
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub sprite: String,
+    pub source: Rect,
+    // In seconds.
+    pub duration: f64,
+    // The cue to play when the frame is entered.
+    pub event: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clip {
+    pub name: String,
+    pub frames: Vec<Frame>,
+    pub looping: bool,
+}
+
+impl Clip {
+    pub fn new(name: &str, looping: bool) -> Self {
+        Self {
+            name: name.to_owned(),
+            frames: Vec::new(),
+            looping,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ClipError {
+    Line(String),
+    MissingSprite(String),
+}
+
+// Sheet <sprite> <columns> <rows>
+// Clip <name> <loop|once>
+// Frame <sprite> <index> <seconds> [cue]
+// A frame's cue plays when the frame is entered, footsteps for example.
+// `size` is the size of a whole sprite, bad lines are skipped.
+fn parse_clips(
+    contents: &str,
+    size: impl Fn(&str) -> Option<Vec2>,
+) -> (HashMap<String, Clip>, Vec<ClipError>) {
+    let mut sheets: HashMap<&str, (u16, u16)> = HashMap::new();
+    let mut clips = HashMap::new();
+    let mut errors = Vec::new();
+    let mut clip: Option<Clip> = None;
+    for line in contents.lines() {
+        let mut tokens = line.split_ascii_whitespace();
+        match tokens.next() {
+            Some("Sheet") => {
+                let sprite = tokens.next();
+                let mut count = || {
+                    tokens
+                        .next()
+                        .and_then(|count| count.parse::<u16>().ok())
+                        .filter(|count| *count > 0)
+                };
+                if let (Some(sprite), Some(columns), Some(rows)) = (sprite, count(), count()) {
+                    sheets.insert(sprite, (columns, rows));
+                } else {
+                    errors.push(ClipError::Line(format!("Could not parse the sheet {line}")));
+                }
+            }
+            Some("Clip") => {
+                if let Some(finished) = clip.take() {
+                    clips.insert(finished.name.clone(), finished);
+                }
+                if let Some(name) = tokens.next() {
+                    let looping = tokens.next() != Some("once");
+                    clip = Some(Clip::new(name, looping));
+                }
+            }
+            Some("Frame") => {
+                let (Some(sprite), Some(Ok(index)), Some(Ok(duration))) = (
+                    tokens.next(),
+                    tokens.next().map(str::parse::<u16>),
+                    tokens.next().map(str::parse::<f64>),
+                ) else {
+                    errors.push(ClipError::Line(format!("Could not parse the frame {line}")));
+                    continue;
+                };
+                if duration <= 0.0 {
+                    errors.push(ClipError::Line(format!(
+                        "Frame duration should be positive: {line}"
+                    )));
+                    continue;
+                }
+                let Some(size) = size(sprite) else {
+                    errors.push(ClipError::MissingSprite(sprite.to_owned()));
+                    continue;
+                };
+                let (columns, rows) = sheets.get(sprite).copied().unwrap_or((1, 1));
+                let (width, height) = (size.x / f32::from(columns), size.y / f32::from(rows));
+                let source = Rect::new(
+                    f32::from(index % columns) * width,
+                    f32::from(index.div_euclid(columns) % rows) * height,
+                    width,
+                    height,
+                );
+                if let Some(clip) = &mut clip {
+                    clip.frames.push(Frame {
+                        sprite: sprite.to_owned(),
+                        source,
+                        duration,
+                        event: tokens.next().map(str::to_owned),
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+    if let Some(finished) = clip.take() {
+        clips.insert(finished.name.clone(), finished);
+    }
+    (clips, errors)
+}
+
+// Playback state of one clip.
+#[derive(Debug, Clone)]
+pub struct Animation {
+    clip: Clip,
+    frame: usize,
+    time: f64,
+    started: bool,
+    finished: bool,
+}
+
+impl Animation {
+    pub const fn new(clip: Clip) -> Self {
+        Self {
+            clip,
+            frame: 0,
+            time: 0.0,
+            started: false,
+            finished: false,
+        }
+    }
+
+    // Restarts only if a different clip is requested.
+    pub fn play(&mut self, clip: &Clip) {
+        if self.clip.name != clip.name {
+            *self = Self::new(clip.clone());
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.clip.name
+    }
+
+    pub const fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn frame(&self) -> Option<&Frame> {
+        self.clip.frames.get(self.frame)
+    }
+
+    // Advances the clip, returns the events of the frames that were entered.
+    pub fn update(&mut self, delta: f64) -> Vec<String> {
+        let mut events = Vec::new();
+        if self.finished || self.clip.frames.is_empty() {
+            return events;
+        }
+        if !self.started {
+            self.started = true;
+            if let Some(event) = self.frame().and_then(|frame| frame.event.clone()) {
+                events.push(event);
+            }
+        }
+        self.time += delta;
+        while let Some(frame) = self.clip.frames.get(self.frame) {
+            if self.time < frame.duration {
+                break;
+            }
+            self.time -= frame.duration;
+            if self.frame + 1 < self.clip.frames.len() {
+                self.frame += 1;
+            } else if self.clip.looping {
+                self.frame = 0;
+            } else {
+                self.time = 0.0;
+                self.finished = true;
+                break;
+            }
+            if let Some(event) = self.frame().and_then(|frame| frame.event.clone()) {
+                events.push(event);
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STAR: &[u8] = include_bytes!("../assets/sprites/star1.png");
+
+    fn frame(event: Option<&str>, duration: f64) -> Frame {
+        Frame {
+            sprite: "char1-idle".to_owned(),
+            source: Rect::new(0.0, 0.0, 10.0, 10.0),
+            duration,
+            event: event.map(str::to_owned),
+        }
+    }
+
+    fn clip(looping: bool) -> Clip {
+        Clip {
+            frames: vec![frame(Some("first"), 0.1), frame(None, 0.2)],
+            ..Clip::new("walk", looping)
+        }
+    }
+
+    #[test]
+    fn advances_frames_by_duration() {
+        let mut animation = Animation::new(clip(true));
+        assert_eq!(animation.update(0.05), vec!["first".to_owned()]);
+        assert_eq!(animation.frame, 0);
+        assert!(animation.update(0.1).is_empty());
+        assert_eq!(animation.frame, 1);
+        // The time left over carries into the next frame.
+        assert!(animation.update(0.1).is_empty());
+        assert_eq!(animation.frame, 1);
+    }
+
+    #[test]
+    fn loops_and_plays_the_first_event_again() {
+        let mut animation = Animation::new(clip(true));
+        animation.update(0.0);
+        assert_eq!(animation.update(0.35), vec!["first".to_owned()]);
+        assert_eq!(animation.frame, 0);
+        assert!(!animation.is_finished());
+    }
+
+    #[test]
+    fn one_shot_clips_stop_on_the_last_frame() {
+        let mut animation = Animation::new(clip(false));
+        animation.update(0.0);
+        assert!(animation.update(1.0).is_empty());
+        assert!(animation.is_finished());
+        assert_eq!(animation.frame, 1);
+        assert!(animation.update(1.0).is_empty());
+        // Asking for the same clip again doesn't restart it.
+        animation.play(&clip(false));
+        assert!(animation.is_finished());
+    }
+
+    #[test]
+    fn parses_clips() {
+        let contents = "Sheet sheet 2 2\n\
+                        Clip run loop\n\
+                        Frame sheet 3 0.1 step\n\
+                        Frame single 0 0.2\n\
+                        Clip land once\n\
+                        Frame single 0 0.1\n";
+        let (clips, errors) = parse_clips(contents, |_| Some(Vec2::new(100.0, 60.0)));
+        assert!(errors.is_empty(), "{errors:?}");
+        let run = clips.get("run").expect("run is parsed");
+        assert!(run.looping);
+        assert_eq!(
+            run.frames
+                .first()
+                .map(|frame| (frame.source, frame.event.as_deref())),
+            Some((Rect::new(50.0, 30.0, 50.0, 30.0), Some("step")))
+        );
+        assert_eq!(
+            run.frames.get(1).map(|frame| frame.source),
+            Some(Rect::new(0.0, 0.0, 100.0, 60.0))
+        );
+        assert!(clips.get("land").is_some_and(|land| !land.looping));
+    }
+
+    #[test]
+    fn skips_bad_clip_lines() {
+        let contents = "Sheet sheet 0 2\n\
+                        Clip run loop\n\
+                        Frame sheet x 0.1\n\
+                        Frame sheet 0 0\n\
+                        Frame missing 0 0.1\n\
+                        Frame sheet 0 0.1\n";
+        let (clips, errors) = parse_clips(contents, |sprite| {
+            (sprite == "sheet").then(|| Vec2::new(10.0, 10.0))
+        });
+        assert_eq!(
+            errors,
+            vec![
+                ClipError::Line("Could not parse the sheet Sheet sheet 0 2".to_owned()),
+                ClipError::Line("Could not parse the frame Frame sheet x 0.1".to_owned()),
+                ClipError::Line("Frame duration should be positive: Frame sheet 0 0".to_owned()),
+                ClipError::MissingSprite("missing".to_owned()),
+            ]
+        );
+        assert_eq!(clips.get("run").map(|run| run.frames.len()), Some(1));
+    }
+
+    #[test]
+    fn accepts_a_real_png() {
+        assert_eq!(validate_png(STAR), Ok(()));
+    }
+
+    #[test]
+    fn rejects_broken_pngs() {
+        assert!(validate_png(b"not a png").is_err());
+        let truncated = STAR.get(..STAR.len() - 100).unwrap_or_default();
+        assert_eq!(validate_png(truncated), Err("truncated chunk"));
+        let mut corrupt = STAR.to_vec();
+        if let Some(byte) = corrupt.get_mut(40) {
+            *byte ^= 0xff;
+        }
+        assert_eq!(validate_png(&corrupt), Err("chunk checksum mismatch"));
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::{HashMap, HashSet};
-use std::fmt::{self, Display};
+use std::fmt::{self, Display, Write};
 
+use macroquad::camera::{set_camera, Camera2D};
 use macroquad::math::Vec2;
 use macroquad::prelude::Rect;
 
@@ -9,9 +10,12 @@ use crate::common::Direction;
 use crate::sprite::Sprites;
 
 // by z-index, I guess
+#[derive(Clone)]
 pub struct StaticLayers {
     layer: HashMap<i32, Vec<StaticEntity>>,
     used_layers: HashSet<i32>,
+    // Layers without settings move with the gameplay.
+    settings: HashMap<i32, LayerSettings>,
 }
 
 impl StaticLayers {
@@ -19,13 +23,29 @@ impl StaticLayers {
         Self {
             layer: HashMap::new(),
             used_layers: HashSet::new(),
+            settings: HashMap::new(),
         }
     }
 
+    // Back to front.
+    pub fn sorted_layers(&self) -> Vec<i32> {
+        let mut layers: Vec<i32> = self.used_layers.iter().copied().collect();
+        layers.sort_unstable();
+        layers
+    }
+
+    pub fn layer_settings(&self, z_index: i32) -> LayerSettings {
+        self.settings.get(&z_index).copied().unwrap_or_default()
+    }
+
+    pub fn set_layer_settings(&mut self, z_index: i32, settings: LayerSettings) {
+        self.settings.insert(z_index, settings);
+    }
+
     pub fn add_entity(&mut self, z_index: i32, entity: StaticEntity) {
         let is_new_layer = self.used_layers.insert(z_index);
         if is_new_layer {
-            println!("Creating a new z_index layer {}", z_index);
+            println!("Creating a new z_index layer {z_index}");
             let new_layer: Vec<StaticEntity> = vec![entity];
             self.layer.insert(z_index, new_layer);
         } else {
@@ -36,16 +56,32 @@ impl StaticLayers {
         }
     }
 
-    pub fn draw(&self, sprites: &Sprites) {
-        for layer in &self.used_layers {
+    // Every layer is drawn with its own parallax camera, `camera` is the
+    // gameplay one and is set again afterwards.
+    pub fn draw(&self, sprites: &Sprites, camera: &Camera2D) {
+        self.each_layer(camera, |entity| sprites.draw(&entity.sprite, entity.pos));
+    }
+
+    pub fn draw_colliders(&self, camera: &Camera2D) {
+        self.each_layer(camera, |entity| {
+            if let Some(collider) = &entity.collider {
+                collider.draw(entity.pos);
+            }
+        });
+    }
+
+    fn each_layer(&self, camera: &Camera2D, mut draw: impl FnMut(&StaticEntity)) {
+        for z_index in self.sorted_layers() {
             let layer = self
                 .layer
-                .get(layer)
+                .get(&z_index)
                 .expect("Unexisting layer in StaticLayers::used_layers");
+            set_camera(&self.layer_settings(z_index).camera(camera));
             for entity in layer {
-                entity.debug(sprites);
+                draw(entity);
             }
         }
+        set_camera(camera);
     }
 
     pub fn get_collision_point(
@@ -65,7 +101,7 @@ impl StaticLayers {
 
             let colliders: Vec<(Rect, &StaticEntity)> = layer
                 .iter()
-                .filter(|collider| collider.collider.is_some())
+                .filter(|entity| entity.collider.is_some())
                 .map(|entity| {
                     (
                         entity
@@ -78,24 +114,30 @@ impl StaticLayers {
                     )
                 })
                 .collect();
-            for step in 0..length.floor() as i32 {
-                let step_offset_x = Vec2::new(step as f32 * direction.x, 0.0) + start_position;
+            let steps = (0..u16::MAX)
+                .map(f32::from)
+                .take_while(|step| *step < length.floor());
+            for step in steps {
+                let step_offset_x = Vec2::new(step * direction.x, 0.0) + start_position;
                 let temp_collider_x = collider.rect().offset(step_offset_x);
-                let step_offset_y = Vec2::new(0.0, step as f32 * direction.y) + start_position;
+                let step_offset_y = Vec2::new(0.0, step * direction.y) + start_position;
                 let temp_collider_y = collider.rect().offset(step_offset_y);
                 for (check_against, entity) in &colliders {
-                    if let Some(collision_rect) = check_against.intersect(temp_collider_x) {
-                        if direction.x > 0.0 {
-                            return Some((entity, Direction::Right, step_offset_x));
+                    if check_against.intersect(temp_collider_x).is_some() {
+                        let side = if direction.x > 0.0 {
+                            Direction::Right
                         } else {
-                            return Some((entity, Direction::Left, step_offset_x));
-                        }
-                    } else if let Some(collision_rect) = check_against.intersect(temp_collider_y) {
-                        if direction.y > 0.0 {
-                            return Some((entity, Direction::Bottom, step_offset_y));
+                            Direction::Left
+                        };
+                        return Some((entity, side, step_offset_x));
+                    }
+                    if check_against.intersect(temp_collider_y).is_some() {
+                        let side = if direction.y > 0.0 {
+                            Direction::Bottom
                         } else {
-                            return Some((entity, Direction::Top, step_offset_y));
-                        }
+                            Direction::Top
+                        };
+                        return Some((entity, side, step_offset_y));
                     }
                 }
             }
@@ -107,7 +149,7 @@ impl StaticLayers {
         let z_index = 0;
         let is_new_layer = self.used_layers.insert(z_index);
         if is_new_layer {
-            println!("Creating a new z_index layer {}", z_index);
+            println!("Creating a new z_index layer {z_index}");
             let new_layer: Vec<StaticEntity> = vec![entity];
             self.layer.insert(z_index, new_layer);
         } else {
@@ -139,24 +181,115 @@ impl StaticLayers {
         }
         None
     }
+
+    // Keeps the entities named `name` visible, but nothing collides with them.
+    pub fn remove_collider(&mut self, name: &str) {
+        for entity in self.layer.values_mut().flatten() {
+            if entity.sprite == name {
+                entity.collider = None;
+            }
+        }
+    }
+
+    // Bottom edge of the lowest collider, there is nothing to stand on below it.
+    pub fn lowest_point(&self) -> Option<f32> {
+        self.layer
+            .values()
+            .flatten()
+            .filter_map(|entity| {
+                let collider = entity.collider.as_ref()?;
+                Some(entity.pos.y + collider.pos.y + collider.height)
+            })
+            .reduce(f32::max)
+    }
 }
 
 impl Display for StaticLayers {
-    #[allow(clippy::unwrap_in_result)]
+    // Layers without entities are kept when they have settings.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut layers: Vec<i32> = self
+            .settings
+            .iter()
+            .filter(|(_, settings)| **settings != LayerSettings::default())
+            .map(|(z_index, _)| *z_index)
+            .chain(self.used_layers.iter().copied())
+            .collect();
+        layers.sort_unstable();
+        layers.dedup();
         let mut contents = String::new();
-        for layer in &self.used_layers {
-            let mut layer_str: String = format!("StaticLayer {}:\n", layer);
-            let layer = self
-                .layer
-                .get(layer)
-                .expect("Unexisting layer in StaticLayers::used_layers");
-            for entity in layer {
-                layer_str.push_str(&format!("{}\n", entity));
+        for z_index in layers {
+            let settings = self.layer_settings(z_index);
+            let mut layer_str: String = if settings == LayerSettings::default() {
+                format!("StaticLayer {z_index}:\n")
+            } else {
+                format!("StaticLayer {z_index}: {settings}\n")
+            };
+            for entity in self.layer.get(&z_index).into_iter().flatten() {
+                writeln!(layer_str, "{entity}")?;
             }
-            contents.push_str(&format!("{}\n", &layer_str));
+            writeln!(contents, "{layer_str}")?;
         }
-        write!(f, "{}", contents)
+        write!(f, "{contents}")
+    }
+}
+
+// How a layer moves relative to the camera. Parallax 1 moves with the
+// gameplay, 0 stays on screen, in between is the distant sky. Scale shrinks
+// the layer around the camera target.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayerSettings {
+    pub parallax: f32,
+    pub scale: f32,
+}
+
+impl Default for LayerSettings {
+    fn default() -> Self {
+        Self {
+            parallax: 1.0,
+            scale: 1.0,
+        }
+    }
+}
+
+impl LayerSettings {
+    // Parses what follows the z-index in `StaticLayer -1: parallax:0.5 scale:0.8`.
+    pub fn parse(rest: &str) -> Result<Self, String> {
+        let mut settings = Self::default();
+        for token in rest.split_ascii_whitespace() {
+            let (key, value) = token
+                .split_once(':')
+                .ok_or_else(|| format!("Expected key:value, got {token}"))?;
+            let value = value
+                .parse::<f32>()
+                .map_err(|_| format!("Could not parse the f32 {value}"))?;
+            match key {
+                "parallax" => settings.parallax = value,
+                "scale" if value > 0.0 => settings.scale = value,
+                "scale" => return Err(format!("scale must be positive, got {value}")),
+                _ => return Err(format!("Unknown StaticLayer field {token}")),
+            }
+        }
+        Ok(settings)
+    }
+
+    pub fn camera(self, camera: &Camera2D) -> Camera2D {
+        Camera2D {
+            target: camera.target * self.parallax,
+            zoom: camera.zoom * self.scale,
+            ..*camera
+        }
+    }
+
+    // Converts a point on the screen into layer coordinates, through the
+    // layer's own version of the gameplay `camera`, rotation included.
+    pub fn to_layer(self, screen: Vec2, camera: &Camera2D) -> Vec2 {
+        self.camera(camera).screen_to_world(screen)
+    }
+}
+
+impl Display for LayerSettings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "parallax:{} scale:{}", self.parallax, self.scale)
     }
 }
 
@@ -185,11 +318,39 @@ impl StaticEntity {
         }
     }
 
-    pub fn debug(&self, sprites: &Sprites) {
-        sprites.draw(&self.sprite, self.pos);
-        if let Some(collider) = &self.collider {
-            collider.draw(self.pos);
+    // Parses the inside of `StaticEntity { sprite, x:0, y:0, Collider { x:0, y:0, w:0, h:0 } }`,
+    // the position is optional for the entity palette.
+    pub fn parse(rest: &str) -> Result<Self, String> {
+        let mut tokens = rest
+            .split_ascii_whitespace()
+            .filter(|e| e != &"{" && e != &"}")
+            .map(|e| e.trim_matches(','));
+        let sprite = tokens.next().ok_or("StaticEntity without a sprite")?;
+        let mut pos = Vec2::new(0.0, 0.0);
+        let mut collider = [0f32; 4];
+        let mut in_collider = false;
+        for token in tokens {
+            if token == "Collider" {
+                in_collider = true;
+                continue;
+            }
+            let (key, value) = token
+                .split_once(':')
+                .ok_or_else(|| format!("Expected key:value, got {token}"))?;
+            let value = value
+                .parse::<f32>()
+                .map_err(|_| format!("Could not parse the f32 {value}"))?;
+            match (in_collider, key) {
+                (false, "x") => pos.x = value,
+                (false, "y") => pos.y = value,
+                (true, "x") => collider[0] = value,
+                (true, "y") => collider[1] = value,
+                (true, "w") => collider[2] = value,
+                (true, "h") => collider[3] = value,
+                _ => return Err(format!("Unknown StaticEntity field {key}")),
+            }
         }
+        Ok(Self::new(pos, sprite.to_owned(), Collider::from(collider)))
     }
 }
 
@@ -0,0 +1,69 @@
+// Small text files that have to survive a restart, like settings. Natively
+// they live in the data folder, on the web in localStorage (see storage.js).
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+#[cfg(not(target_arch = "wasm32"))]
+const DATA_FOLDER: &str = "./data";
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load(name: &str) -> Option<String> {
+    fs::read_to_string(Path::new(DATA_FOLDER).join(name)).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(name: &str, contents: &str) -> Result<(), String> {
+    let path = Path::new(DATA_FOLDER).join(name);
+    fs::write(&path, contents).map_err(|why| format!("couldn't write {}: {}", path.display(), why))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove(name: &str) -> Result<(), String> {
+    let path = Path::new(DATA_FOLDER).join(name);
+    match fs::remove_file(&path) {
+        Err(why) if why.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("couldn't remove {}: {}", path.display(), why))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    fn storage_set(key: *const u8, key_len: usize, value: *const u8, value_len: usize);
+    // -1 when there is nothing stored under the key.
+    fn storage_len(key: *const u8, key_len: usize) -> i32;
+    fn storage_get(key: *const u8, key_len: usize, buffer: *mut u8, buffer_len: usize);
+    fn storage_remove(key: *const u8, key_len: usize);
+}
+
+// storage.js checks this against its own version.
+#[cfg(target_arch = "wasm32")]
+#[no_mangle]
+pub extern "C" fn kaika_storage_crate_version() -> u32 {
+    2 << 16
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn load(name: &str) -> Option<String> {
+    let len = unsafe { storage_len(name.as_ptr(), name.len()) };
+    let len = usize::try_from(len).ok()?;
+    let mut buffer = vec![0u8; len];
+    unsafe { storage_get(name.as_ptr(), name.len(), buffer.as_mut_ptr(), len) };
+    String::from_utf8(buffer).ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save(name: &str, contents: &str) -> Result<(), String> {
+    unsafe { storage_set(name.as_ptr(), name.len(), contents.as_ptr(), contents.len()) };
+    Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn remove(name: &str) -> Result<(), String> {
+    unsafe { storage_remove(name.as_ptr(), name.len()) };
+    Ok(())
+}
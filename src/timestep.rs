@@ -0,0 +1,172 @@
+use std::fmt::{self, Display};
+
+use crate::common::narrow;
+use crate::overlay::{DebugPanel, Panels};
+
+// Turns frame times into whole fixed ticks, so the simulation runs the same
+// at any frame rate. What is left over tells how far rendering is between
+// the last two ticks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FixedTimestep {
+    // Seconds per tick.
+    tick: f64,
+    // Ticks past this in one frame are dropped, after a long hitch the game
+    // slows down instead of spending seconds catching up.
+    pub max_ticks: u32,
+    accumulator: f64,
+}
+
+impl FixedTimestep {
+    pub fn new(rate: f64, max_ticks: u32) -> Self {
+        Self {
+            tick: 1.0 / rate,
+            max_ticks,
+            accumulator: 0.0,
+        }
+    }
+
+    pub const fn tick(&self) -> f64 {
+        self.tick
+    }
+
+    pub fn rate(&self) -> f64 {
+        1.0 / self.tick
+    }
+
+    pub fn set_rate(&mut self, rate: f64) {
+        self.tick = 1.0 / rate;
+        self.accumulator = 0.0;
+    }
+
+    // How many ticks to run for a frame that took `delta` seconds.
+    pub fn advance(&mut self, delta: f64) -> u32 {
+        self.accumulator += delta.max(0.0);
+        for ticks in 0..self.max_ticks {
+            if self.accumulator < self.tick {
+                return ticks;
+            }
+            self.accumulator -= self.tick;
+        }
+        // The time past the limit is dropped.
+        self.accumulator %= self.tick;
+        self.max_ticks
+    }
+
+    // 0 right on the last tick, almost 1 just before the next one.
+    pub fn alpha(&self) -> f32 {
+        narrow(self.accumulator / self.tick)
+    }
+}
+
+// Slow motion, pause and stepping for gameplay time. The UI, the audio and
+// the camera debug controls keep running in real time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeControl {
+    pub scale: f64,
+    pub paused: bool,
+    // A single tick to run while paused.
+    step: bool,
+    // Ticks run so far.
+    pub ticks: u64,
+}
+
+impl TimeControl {
+    pub const fn new() -> Self {
+        Self {
+            scale: 1.0,
+            paused: false,
+            step: false,
+            ticks: 0,
+        }
+    }
+
+    pub const fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    // Pauses if needed and runs one tick on the next frame.
+    pub const fn step(&mut self) {
+        self.paused = true;
+        self.step = true;
+    }
+
+    pub fn slower(&mut self) {
+        self.scale = (self.scale / 2.0).max(MIN_SCALE);
+    }
+
+    pub fn faster(&mut self) {
+        self.scale = (self.scale * 2.0).min(MAX_SCALE);
+    }
+
+    // Paused or not at normal speed.
+    pub fn is_altered(&self) -> bool {
+        self.paused || (self.scale - 1.0).abs() > f64::EPSILON
+    }
+
+    pub fn register_panels<'a>(&'a self, panels: &mut Panels<'a>) {
+        panels.register("time", self);
+    }
+
+    // How many ticks to run for a frame that took `delta` real seconds.
+    pub fn advance(&mut self, timestep: &mut FixedTimestep, delta: f64) -> u32 {
+        let ticks = if self.paused {
+            u32::from(std::mem::take(&mut self.step))
+        } else {
+            timestep.advance(delta * self.scale)
+        };
+        self.ticks += u64::from(ticks);
+        ticks
+    }
+}
+
+impl DebugPanel for TimeControl {
+    fn lines(&self) -> Vec<String> {
+        vec![self.to_string()]
+    }
+}
+
+impl Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "time x{} tick {}", self.scale, self.ticks)?;
+        if self.paused {
+            write!(f, " paused")?;
+        }
+        Ok(())
+    }
+}
+
+const MIN_SCALE: f64 = 1.0 / 16.0;
+const MAX_SCALE: f64 = 4.0;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carries_the_rest_over() {
+        let mut timestep = FixedTimestep::new(4.0, 5);
+        assert_eq!(timestep.advance(0.375), 1);
+        assert!((timestep.alpha() - 0.5).abs() < f32::EPSILON);
+        assert_eq!(timestep.advance(0.125), 1);
+        assert!(timestep.alpha().abs() < f32::EPSILON);
+        assert_eq!(timestep.advance(0.125), 0);
+    }
+
+    #[test]
+    fn drops_ticks_past_the_limit() {
+        let mut timestep = FixedTimestep::new(4.0, 3);
+        assert_eq!(timestep.advance(10.1), 3);
+        assert!(timestep.alpha() < 1.0);
+        assert_eq!(timestep.advance(0.0), 0);
+    }
+
+    #[test]
+    fn alpha_stays_below_one() {
+        let mut timestep = FixedTimestep::new(60.0, 8);
+        for delta in [0.0, 0.001, 0.016, 0.017, 0.05, 0.1, 0.5, 3.0, -1.0] {
+            timestep.advance(delta);
+            let alpha = timestep.alpha();
+            assert!((0.0..1.0).contains(&alpha), "{delta}: {alpha}");
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::fmt::{self, Display};
+
+use macroquad::math::Vec2;
+use macroquad::prelude::Rect;
+
+use crate::ability::Ability;
+use crate::collider::Collider;
+use crate::sprite::Sprites;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TriggerAction {
+    GrantAbility(Ability),
+    // Crossfades to another music track.
+    Music(String),
+    // A pickup with an id unique within the level, saved once collected.
+    Collect(String),
+    // Completes the level and unlocks the named one.
+    Finish(String),
+}
+
+impl TriggerAction {
+    fn parse(key: &str, value: &str) -> Option<Self> {
+        match key {
+            "grant" => Ability::from_name(value).map(Self::GrantAbility),
+            "music" => Some(Self::Music(value.to_owned())),
+            "collect" => Some(Self::Collect(value.to_owned())),
+            "finish" => Some(Self::Finish(value.to_owned())),
+            _ => None,
+        }
+    }
+}
+
+impl Display for TriggerAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::GrantAbility(ability) => write!(f, "grant:{}", ability.name()),
+            Self::Music(id) => write!(f, "music:{id}"),
+            Self::Collect(id) => write!(f, "collect:{id}"),
+            Self::Finish(next) => write!(f, "finish:{next}"),
+        }
+    }
+}
+
+// An area in the level that does something once the player touches it.
+// With a sprite it is drawn and picked up, without one it is invisible.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Trigger {
+    pub action: TriggerAction,
+    pub collider: Collider,
+    pub sprite: Option<String>,
+    pub fired: bool,
+}
+
+impl Trigger {
+    pub const fn new(action: TriggerAction, collider: Collider, sprite: Option<String>) -> Self {
+        Self {
+            action,
+            collider,
+            sprite,
+            fired: false,
+        }
+    }
+
+    pub fn overlaps(&self, rect: Rect) -> bool {
+        self.collider.rect().overlaps(&rect)
+    }
+
+    pub fn draw(&self, sprites: &Sprites) {
+        if self.fired {
+            return;
+        }
+        if let Some(sprite) = &self.sprite {
+            sprites.draw(sprite, self.collider.pos);
+        }
+    }
+
+    pub fn debug(&self) {
+        self.collider.draw(Vec2::new(0.0, 0.0));
+    }
+
+    // Parses the inside of `Trigger { grant:dash, sprite:star3, x:0, y:0, w:80, h:80 }`.
+    pub fn parse(rest: &str) -> Option<Self> {
+        let mut action = None;
+        let mut sprite = None;
+        let mut area = [0f32; 4];
+        for pair in rest
+            .split_ascii_whitespace()
+            .filter(|e| e != &"{" && e != &"}")
+        {
+            let (key, value) = pair.trim_matches(',').split_once(':')?;
+            match key {
+                "sprite" => sprite = Some(value.to_owned()),
+                "x" => area[0] = value.parse().ok()?,
+                "y" => area[1] = value.parse().ok()?,
+                "w" => area[2] = value.parse().ok()?,
+                "h" => area[3] = value.parse().ok()?,
+                _ => action = action.or_else(|| TriggerAction::parse(key, value)),
+            }
+        }
+        Some(Self::new(action?, Collider::from(area), sprite))
+    }
+}
+
+impl Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Collider { pos, width, height } = &self.collider;
+        write!(f, "Trigger {{ {}, ", self.action)?;
+        if let Some(sprite) = &self.sprite {
+            write!(f, "sprite:{sprite}, ")?;
+        }
+        write!(f, "x:{}, y:{}, w:{}, h:{} }}", pos.x, pos.y, width, height)
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-use crate::common::lerp;
+use crate::common::{lerp, narrow, whole};
 
 pub const TWEEN_PERIOD: f32 = 1000.0 / 30.0;
 
@@ -26,34 +26,42 @@ impl Tween {
         self.time = 0.0;
         self.value = *self
             .waveform
-            .get(0)
+            .first()
             .expect("tween should always have a waveform");
         self.stopped = true;
     }
 
-    pub fn set_offset(&mut self, offset: usize) {
-        self.time = offset as f32 * TWEEN_PERIOD;
+    // Milliseconds from the start to step `offset` of the waveform.
+    pub fn offset_time(offset: u16) -> f32 {
+        f32::from(offset) * TWEEN_PERIOD
+    }
+
+    pub fn set_offset(&mut self, offset: u16) {
+        self.time = Self::offset_time(offset);
         self.value();
     }
 
     pub fn update(&mut self, delta: f64) {
         if !self.stopped {
-            self.time += 1000.0 * delta as f32;
+            self.time += 1000.0 * narrow(delta);
         }
     }
 
     pub fn is_over(&self) -> bool {
-        let time_length = (self.waveform.len()) as f32 * TWEEN_PERIOD;
-        self.time >= time_length
+        whole(self.time / TWEEN_PERIOD) >= self.waveform.len()
     }
 
     pub fn value(&mut self) -> f32 {
         if !self.stopped {
             let period = self.time / TWEEN_PERIOD;
-            let step = period.ceil() as usize;
-            let fraction = period.fract();
+            let step = whole(period.ceil());
+            // 1 on a step boundary, where `fract` would jump back to the previous value.
+            let fraction = 1.0 - (period.ceil() - period);
             if let Some(value) = self.waveform.get(step) {
-                let previous_value = self.waveform.get(step - 1).unwrap_or(&0.0);
+                let previous_value = step
+                    .checked_sub(1)
+                    .and_then(|previous| self.waveform.get(previous))
+                    .unwrap_or(&0.0);
                 let lerp_value = lerp(*previous_value, *value, fraction);
                 self.value = lerp_value;
             } else {
@@ -1,24 +1,95 @@
 use macroquad::prelude::*;
 use macroquad::ui;
 
-use crate::world::World;
+use crate::camera::Follow;
+use crate::camera_zone::CameraZone;
+use crate::emitter::{Emitter, EMITTER_HANDLE};
+use crate::input::Action;
+use crate::static_layers::LayerSettings;
+use crate::world::{World, WorldState};
 
 impl World {
-    pub(super) fn edit_setup(&mut self) {
-        self.load_level();
+    // Back to the level and the view as they were when Play started. Play
+    // only ever ran on a copy, unsaved edits are still here.
+    pub(super) const fn edit_setup(&mut self) {
+        if let Some(camera) = self.edit_camera.take() {
+            self.main_camera = camera;
+        }
+    }
+
+    // Remembers the view for coming back, see `edit_setup`.
+    pub(super) const fn edit_leave(&mut self) {
+        self.edit_camera = Some(self.main_camera);
+    }
+
+    // F5 plays from the mouse, dying respawns there too.
+    fn edit_play_here(&mut self, mouse: Vec2) {
+        self.edit_leave();
+        self.play_setup();
+        if let Some(simulation) = &mut self.simulation {
+            let center = simulation.player.collider.center();
+            simulation.respawn_at(mouse - center);
+            self.main_camera
+                .set_follow(Some(Follow::new(simulation.spawn_pos)));
+        }
+        self.state = WorldState::Play;
     }
 
     pub(super) fn edit_input(&mut self) {
-        if is_key_pressed(KeyCode::Key1) {
-            self.entities.load_entities();
+        if self.controls.pressed(Action::EditorReloadPalette) {
+            if let Err(why) = self.entities.load_entities() {
+                eprintln!("ERROR: {why}");
+            }
         }
 
-        let z_index = 0;
         let mouse = self.main_camera.mouse_world_position();
-        let lmb = is_mouse_button_pressed(MouseButton::Left);
+        if self.controls.pressed(Action::EditorPlayHere) {
+            self.edit_play_here(mouse);
+            return;
+        }
+        let lmb = self.controls.pressed(Action::EditorPlace);
+
+        let layer = self.edit_layer_input();
+
+        if self.controls.pressed(Action::EditorRemove) {
+            let emitters = self.level.emitters.len();
+            self.level
+                .emitters
+                .retain(|emitter| emitter.pos.distance(mouse) > EMITTER_HANDLE);
+            if emitters == self.level.emitters.len() {
+                if let Some(index) = self.level.zone_at(mouse) {
+                    self.level.camera_zones.remove(index);
+                }
+            }
+        }
+        if let Some(zone) = self
+            .level
+            .zone_at(mouse)
+            .and_then(|index| self.level.camera_zones.get_mut(index))
+        {
+            if self.controls.pressed(Action::EditorZoneBounds) {
+                zone.bounds = !zone.bounds;
+            }
+            if self.controls.pressed(Action::EditorZoneZoom) {
+                zone.cycle_zoom();
+            }
+            if self.controls.pressed(Action::EditorZoneTarget) {
+                zone.toggle_target();
+            }
+        }
 
         if let Some(chosen) = self.entities.ui() {
             self.chosen_entity = Some(chosen);
+            self.chosen_emitter = None;
+            self.placing_zone = false;
+        } else if let Some(sound) = self.emitter_ui() {
+            self.chosen_emitter = Some(sound);
+            self.chosen_entity = None;
+            self.placing_zone = false;
+        } else if ui::root_ui().button(None, "Camera zone") {
+            self.placing_zone = true;
+            self.chosen_entity = None;
+            self.chosen_emitter = None;
         } else if ui::root_ui().button(None, "Save the level") {
             self.save_level();
         } else if let Some(entity) = self.chosen_entity {
@@ -28,27 +99,101 @@ impl World {
                 .expect("Tried to get unexisting entity, chosen_entity set incorrectly");
             if lmb {
                 let mut entity = entity.clone();
+                if entity.collider.is_none() {
+                    entity.collider = self
+                        .sprites
+                        .meta(&entity.sprite)
+                        .and_then(|meta| meta.collider.clone());
+                }
                 let offset = if let Some(collider) = &entity.collider {
                     collider.center()
                 } else {
                     self.sprites
-                        .get_sprite(&entity.sprite)
+                        .pivot(&entity.sprite)
                         .expect("Chosen entity should have a sprite")
-                        .size()
-                        * 0.5
                 };
-                entity.pos = mouse - offset;
-                if entity.sprite.ends_with("spawn") {
-                    self.static_layers.replace(entity);
+                if self.sprites.has_tag(&entity.sprite, "spawn") {
+                    // The player always spawns on the gameplay layer.
+                    entity.pos = mouse - offset;
+                    self.level.static_layers.replace(entity);
                 } else {
-                    self.static_layers.add_entity(z_index, entity);
+                    let (x, y) = mouse_position();
+                    entity.pos = layer.to_layer(vec2(x, y), &self.camera2d()) - offset;
+                    self.level.static_layers.add_entity(self.edit_layer, entity);
                 }
             }
+        } else if let Some(sound) = &self.chosen_emitter {
+            if lmb {
+                self.level.emitters.push(Emitter::new(sound.clone(), mouse));
+            }
+        } else if self.placing_zone {
+            if lmb {
+                self.zone_start = Some(mouse);
+            }
+            if self.controls.released(Action::EditorPlace) {
+                if let Some(start) = self.zone_start.take() {
+                    let area = zone_area(start, mouse);
+                    if area.w > MIN_ZONE_SIZE && area.h > MIN_ZONE_SIZE {
+                        self.level.camera_zones.push(CameraZone::new(area));
+                    }
+                }
+            }
+        }
+    }
+
+    // Picks the layer to place on and changes its parallax.
+    fn edit_layer_input(&mut self) -> LayerSettings {
+        if self.controls.pressed(Action::EditorLayerDown) {
+            self.edit_layer -= 1;
+        }
+        if self.controls.pressed(Action::EditorLayerUp) {
+            self.edit_layer += 1;
+        }
+        let mut layer = self.level.static_layers.layer_settings(self.edit_layer);
+        let down = self.controls.pressed(Action::EditorParallaxDown);
+        if down || self.controls.pressed(Action::EditorParallaxUp) {
+            let step = if down { -0.1 } else { 0.1 };
+            layer.parallax = ((layer.parallax + step) * 10.0).round() / 10.0;
+            self.level
+                .static_layers
+                .set_layer_settings(self.edit_layer, layer);
         }
+        layer
     }
-    pub(super) fn edit_update(&mut self) {}
-    pub(super) fn edit_draw(&mut self) {
-        self.static_layers.draw(&self.sprites);
+
+    // One button per sound, right click removes the emitter under the mouse.
+    fn emitter_ui(&self) -> Option<String> {
+        ui::root_ui().label(None, "Emitters");
+        let mut selection = None;
+        for id in self.audio.sound_ids() {
+            if ui::root_ui().button(None, format!("emitter {id}")) {
+                selection = Some(id.to_owned());
+            }
+        }
+        selection
+    }
+    pub(super) fn edit_draw(&self) {
+        self.level
+            .static_layers
+            .draw(&self.sprites, &self.camera2d());
+        self.level.static_layers.draw_colliders(&self.camera2d());
+        for trigger in &self.level.triggers {
+            trigger.draw(&self.sprites);
+            trigger.debug();
+        }
+        for checkpoint in &self.level.checkpoints {
+            checkpoint.draw(&self.sprites);
+            checkpoint.debug();
+        }
+        for emitter in &self.level.emitters {
+            emitter.debug();
+        }
+        for zone in &self.level.camera_zones {
+            zone.debug();
+        }
+        if let Some(Rect { x, y, w, h }) = self.level.bounds {
+            draw_rectangle_lines(x, y, w, h, 10.0, color_u8!(200, 80, 80, 255));
+        }
         if let Some(entity) = self.chosen_entity {
             let mouse = self.main_camera.mouse_world_position();
             let entity = self
@@ -57,6 +202,56 @@ impl World {
                 .expect("Tried to get unexisting entity, chosen_entity set incorrectly");
 
             self.sprites.draw_at_center(&entity.sprite, mouse);
+        } else if let Some(sound) = &self.chosen_emitter {
+            let mouse = self.main_camera.mouse_world_position();
+            Emitter::new(sound.clone(), mouse).debug();
+        } else if let Some(start) = self.zone_start {
+            let mouse = self.main_camera.mouse_world_position();
+            CameraZone::new(zone_area(start, mouse)).debug();
+        }
+
+        set_default_camera();
+        let layer = self.level.static_layers.layer_settings(self.edit_layer);
+        let bindings = &self.controls.bindings;
+        draw_text(
+            &format!(
+                "layer {} ({} and {}), parallax {} ({} and {}), scale {}",
+                self.edit_layer,
+                bindings.label(Action::EditorLayerDown),
+                bindings.label(Action::EditorLayerUp),
+                layer.parallax,
+                bindings.label(Action::EditorParallaxDown),
+                bindings.label(Action::EditorParallaxUp),
+                layer.scale
+            ),
+            10.0,
+            screen_height() - 20.0,
+            20.0,
+            color_u8!(255, 255, 255, 255),
+        );
+        if self.placing_zone {
+            draw_text(
+                &format!(
+                    "Drag to add a camera zone. Over a zone: {} bounds, {} zoom, {} fixed target, {} removes",
+                    bindings.label(Action::EditorZoneBounds),
+                    bindings.label(Action::EditorZoneZoom),
+                    bindings.label(Action::EditorZoneTarget),
+                    bindings.label(Action::EditorRemove)
+                ),
+                10.0,
+                screen_height() - 40.0,
+                20.0,
+                color_u8!(255, 255, 255, 255),
+            );
         }
     }
 }
+
+// Zones smaller than this are most likely misclicks.
+const MIN_ZONE_SIZE: f32 = 50.0;
+
+fn zone_area(start: Vec2, end: Vec2) -> Rect {
+    let min = start.min(end);
+    let size = (start - end).abs();
+    Rect::new(min.x, min.y, size.x, size.y)
+}
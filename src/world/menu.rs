@@ -0,0 +1,287 @@
+use macroquad::prelude::*;
+
+use crate::audio::Bus;
+use crate::input::{Action, Binding};
+use crate::save::SLOTS;
+use crate::world::save::read_slot;
+use crate::world::{World, WorldState, LEVEL_NAME};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MenuItem {
+    Resume,
+    Restart,
+    // Left and right pick the slot the next three items act on.
+    Slot,
+    // Loads the slot at its last checkpoint.
+    Continue,
+    // Loads the slot at the start of the level.
+    Load,
+    Delete,
+    // Left and right change the volume, select toggles mute.
+    Volume(Bus),
+    // Left and right scale camera shake, zoom punches and kicks.
+    ScreenShake,
+    // Left and right pick one of the bindings of the action, select waits for
+    // a key or button that replaces it.
+    Bind(Action),
+}
+
+const MENU_ITEMS: [MenuItem; 16] = [
+    MenuItem::Resume,
+    MenuItem::Restart,
+    MenuItem::Slot,
+    MenuItem::Continue,
+    MenuItem::Load,
+    MenuItem::Delete,
+    MenuItem::Volume(Bus::Master),
+    MenuItem::Volume(Bus::Music),
+    MenuItem::Volume(Bus::Sfx),
+    MenuItem::Volume(Bus::Ui),
+    MenuItem::ScreenShake,
+    MenuItem::Bind(Action::GAMEPLAY[0]),
+    MenuItem::Bind(Action::GAMEPLAY[1]),
+    MenuItem::Bind(Action::GAMEPLAY[2]),
+    MenuItem::Bind(Action::GAMEPLAY[3]),
+    MenuItem::Bind(Action::GAMEPLAY[4]),
+];
+
+const VOLUME_STEP: f32 = 0.1;
+
+impl World {
+    // Pauses Play, the level stays visible behind the menu.
+    pub(super) fn menu_open(&mut self) {
+        self.menu_selection = 0;
+        self.menu_rebinding = None;
+        self.menu_binding_slot = 0;
+        self.menu_slot = self.save_slot;
+        self.menu_refresh_slot();
+        self.state = WorldState::Menu;
+        self.audio.play("menu-open");
+    }
+
+    fn menu_close(&mut self) {
+        self.state = WorldState::Play;
+        self.audio.play("menu-close");
+        // Don't count the time spent in the menu as a frame.
+        self.time.overall = get_time();
+    }
+
+    pub(super) fn menu_input(&mut self) {
+        if let Some(action) = self.menu_rebinding {
+            self.menu_rebind(action);
+            return;
+        }
+        if self.controls.pressed(Action::Pause) {
+            self.menu_close();
+            return;
+        }
+        if self.controls.pressed(Action::MenuUp) {
+            self.menu_selection = self.menu_selection.saturating_sub(1);
+            self.menu_confirm_delete = false;
+            self.menu_binding_slot = 0;
+        }
+        if self.controls.pressed(Action::MenuDown) {
+            self.menu_selection = (self.menu_selection + 1).min(MENU_ITEMS.len() - 1);
+            self.menu_confirm_delete = false;
+            self.menu_binding_slot = 0;
+        }
+        let Some(item) = MENU_ITEMS.get(self.menu_selection).copied() else {
+            return;
+        };
+        let left = self.controls.pressed(Action::MenuLeft);
+        let right = self.controls.pressed(Action::MenuRight);
+        if left || right {
+            let step = if left { -VOLUME_STEP } else { VOLUME_STEP };
+            // Round, so repeated steps don't drift away from tenths.
+            let round = |value: f32| (value * 10.0).round() / 10.0;
+            match item {
+                MenuItem::Volume(bus) => {
+                    let volume = self.audio.mixer.bus(bus).volume + step;
+                    self.audio.mixer.set_volume(bus, round(volume));
+                }
+                MenuItem::ScreenShake => {
+                    let scale = self.camera_effects.scale + step;
+                    self.camera_effects.scale = round(scale).clamp(0.0, 1.0);
+                    // Show what the new setting feels like.
+                    self.camera_effects.add_trauma(0.5);
+                }
+                MenuItem::Slot => {
+                    self.menu_slot = if left {
+                        (self.menu_slot + SLOTS - 1) % SLOTS
+                    } else {
+                        (self.menu_slot + 1) % SLOTS
+                    };
+                    self.menu_refresh_slot();
+                    self.audio.play("ui-select");
+                    return;
+                }
+                MenuItem::Bind(action) => {
+                    let slots = self.controls.bindings.get(action).len() + 1;
+                    self.menu_binding_slot = if left {
+                        (self.menu_binding_slot + slots - 1) % slots
+                    } else {
+                        (self.menu_binding_slot + 1) % slots
+                    };
+                    self.audio.play("ui-select");
+                    return;
+                }
+                MenuItem::Resume
+                | MenuItem::Restart
+                | MenuItem::Continue
+                | MenuItem::Load
+                | MenuItem::Delete => return,
+            }
+            self.save_settings();
+            self.audio.play("ui-select");
+        }
+        if self.controls.pressed(Action::MenuSelect) {
+            self.audio.play("ui-select");
+            match item {
+                MenuItem::Resume => self.menu_close(),
+                MenuItem::Restart => {
+                    self.play_setup();
+                    self.menu_close();
+                }
+                MenuItem::Continue | MenuItem::Load => {
+                    self.load_slot(self.menu_slot, item == MenuItem::Continue);
+                    self.menu_close();
+                }
+                MenuItem::Delete if self.menu_confirm_delete => {
+                    self.delete_slot(self.menu_slot);
+                    self.menu_refresh_slot();
+                }
+                MenuItem::Delete => self.menu_confirm_delete = true,
+                MenuItem::Volume(bus) => {
+                    self.audio.mixer.toggle_mute(bus);
+                    self.save_settings();
+                }
+                MenuItem::ScreenShake | MenuItem::Slot => (),
+                MenuItem::Bind(action) => self.menu_rebinding = Some(action),
+            }
+        }
+    }
+
+    // The next key, mouse, pad or touch button pressed replaces the picked
+    // binding, Escape keeps the old one.
+    fn menu_rebind(&mut self, action: Action) {
+        let Some(binding) = self.controls.pressed_binding() else {
+            return;
+        };
+        self.menu_rebinding = None;
+        if binding != Binding::Key(KeyCode::Escape) {
+            self.controls
+                .bindings
+                .set_slot(action, self.menu_binding_slot, binding);
+            self.save_bindings();
+        }
+        self.audio.play("ui-select");
+    }
+
+    // Reads the picked slot again and forgets a pending delete.
+    fn menu_refresh_slot(&mut self) {
+        self.menu_confirm_delete = false;
+        let save = if self.menu_slot == self.save_slot {
+            self.save_error
+                .clone()
+                .map_or_else(|| Ok(Some(self.save.clone())), Err)
+        } else {
+            read_slot(self.menu_slot)
+        };
+        self.menu_slot_summary = match save {
+            Err(why) => format!("unreadable, {why}"),
+            Ok(None) => "empty".to_owned(),
+            Ok(Some(save)) => {
+                let best = save
+                    .levels
+                    .get(LEVEL_NAME)
+                    .and_then(|progress| progress.best_time)
+                    .map_or(String::new(), |time| format!(", best {time:.2}s"));
+                format!("{}{}", save.summary(), best)
+            }
+        };
+    }
+
+    fn menu_label(&self, item: MenuItem) -> String {
+        match item {
+            MenuItem::Resume => "Resume".to_owned(),
+            MenuItem::Restart => "Restart".to_owned(),
+            MenuItem::Slot => format!(
+                "< slot {}{} > {}",
+                self.menu_slot + 1,
+                if self.menu_slot == self.save_slot {
+                    " (current)"
+                } else {
+                    ""
+                },
+                self.menu_slot_summary
+            ),
+            MenuItem::Continue => "Continue".to_owned(),
+            MenuItem::Load => "Load from the start".to_owned(),
+            MenuItem::Delete if self.menu_confirm_delete => {
+                format!("Delete slot {}? Select again", self.menu_slot + 1)
+            }
+            MenuItem::Delete => "Delete".to_owned(),
+            MenuItem::Volume(bus) => {
+                let settings = self.audio.mixer.bus(bus);
+                format!(
+                    "{} volume < {:.0}% >{}",
+                    bus.name(),
+                    settings.volume * 100.0,
+                    if settings.muted { " (muted)" } else { "" }
+                )
+            }
+            MenuItem::ScreenShake => {
+                format!("screen shake < {:.0}% >", self.camera_effects.scale * 100.0)
+            }
+            MenuItem::Bind(action) if self.menu_rebinding == Some(action) => {
+                format!("{}: press a key, Escape cancels", action.name())
+            }
+            MenuItem::Bind(action) => {
+                let selected = MENU_ITEMS.get(self.menu_selection) == Some(&item);
+                let bindings: Vec<String> = self
+                    .controls
+                    .bindings
+                    .get(action)
+                    .iter()
+                    .map(ToString::to_string)
+                    .chain(["+".to_owned()])
+                    .enumerate()
+                    .map(|(slot, name)| {
+                        if selected && slot == self.menu_binding_slot {
+                            format!("< {name} >")
+                        } else {
+                            name
+                        }
+                    })
+                    .collect();
+                format!("{}: {}", action.name(), bindings.join(", "))
+            }
+        }
+    }
+
+    pub(super) fn menu_draw(&self) {
+        self.play_draw();
+        set_default_camera();
+        draw_rectangle(
+            0.0,
+            0.0,
+            screen_width(),
+            screen_height(),
+            color_u8!(0, 0, 0, 150),
+        );
+        let font_size = (screen_height() * 0.8
+            / (f32::from(u16::try_from(MENU_ITEMS.len()).unwrap_or(u16::MAX)) * 1.2))
+            .min(30.0);
+        let x = screen_width() * 0.35;
+        let mut y = screen_height() * 0.15;
+        for (i, item) in MENU_ITEMS.iter().enumerate() {
+            let color = if i == self.menu_selection {
+                color_u8!(255, 220, 120, 255)
+            } else {
+                color_u8!(200, 200, 200, 255)
+            };
+            draw_text(&self.menu_label(*item), x, y, font_size, color);
+            y += font_size * 1.2;
+        }
+    }
+}
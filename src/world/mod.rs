@@ -1,6 +1,12 @@
 pub mod debug;
 pub mod edit;
+pub mod menu;
+pub mod overlay;
 pub mod play;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod reload;
+pub mod replay;
+pub mod save;
 
 use std::fs;
 use std::fs::File;
@@ -11,12 +17,30 @@ use macroquad::prelude::*;
 
 use crate::audio::Audio;
 use crate::camera::{top_down_camera_controls, Camera};
-use crate::collider::Collider;
+use crate::camera_effects::CameraEffects;
 use crate::entity::Entities;
-use crate::player::Player;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::hot_reload::HotReload;
+use crate::input::{Action, Bindings, Input, BINDINGS_PATH};
+use crate::level::Level;
+use crate::overlay::Overlay;
+use crate::player::PlayerInput;
+use crate::replay::{Playback, Replay};
+use crate::save::{SaveGame, SLOTS};
+use crate::simulation::Simulation;
 use crate::sprite::Sprites;
-use crate::static_layers::StaticEntity;
-use crate::static_layers::StaticLayers;
+use crate::storage;
+use crate::timestep::{FixedTimestep, TimeControl};
+
+pub const LEVEL_PATH: &str = "./data/level0.txt";
+// Progress in the level is saved under this name.
+pub const LEVEL_NAME: &str = "level0";
+// Relative to the data folder, see `storage`.
+const SETTINGS_PATH: &str = "settings.txt";
+// Simulation ticks per second, `TickRate` in the settings overrides it.
+const TICK_RATE: f64 = 60.0;
+// A frame longer than this many ticks slows the game down.
+const MAX_TICKS_PER_FRAME: u32 = 5;
 
 #[allow(clippy::module_name_repetitions)]
 pub enum WorldState {
@@ -31,17 +55,56 @@ pub struct World {
     sprites: Sprites,
     audio: Audio,
 
-    static_layers: StaticLayers,
+    level: Level,
+    save: SaveGame,
+    // Slot `save` is written to.
+    save_slot: usize,
+    // Why the slot could not be read, it stays untouched until deleted.
+    save_error: Option<String>,
 
     time: Time,
+    controls: Input,
+    overlay: Overlay,
+    timestep: FixedTimestep,
+    time_control: TimeControl,
     main_camera: Camera,
+    camera_effects: CameraEffects,
 
     entities: Entities,
 
+    #[cfg(not(target_arch = "wasm32"))]
+    hot_reload: HotReload,
+
     // Play
-    player: Option<Player>,
+    simulation: Option<Simulation>,
+    // Controls held this frame, every tick of the frame uses them.
+    player_input: PlayerInput,
+    recording: Option<Replay>,
+    // Feeds the ticks instead of the controls while it lasts.
+    playback: Option<Playback>,
+    // Menu
+    menu_selection: usize,
+    // Action waiting for a key in the options menu.
+    menu_rebinding: Option<Action>,
+    // Which binding of the selected action is replaced, one past the last
+    // adds a new one.
+    menu_binding_slot: usize,
+    // Slot the load, continue and delete items act on.
+    menu_slot: usize,
+    menu_slot_summary: String,
+    // Delete needs a second select.
+    menu_confirm_delete: bool,
     // Edit
+    // The editor's view while Play runs.
+    edit_camera: Option<Camera>,
     chosen_entity: Option<usize>,
+    // Layer new entities are placed on.
+    edit_layer: i32,
+    // Sound of the emitter to place.
+    chosen_emitter: Option<String>,
+    placing_zone: bool,
+    // Corner where dragging out a camera zone started.
+    zone_start: Option<Vec2>,
 }
 
 impl World {
@@ -51,47 +114,85 @@ impl World {
             audio,
             sprites,
 
-            static_layers: StaticLayers::new(),
+            level: Level::new(),
+            save: SaveGame::new(LEVEL_NAME),
+            save_slot: 0,
+            save_error: None,
 
             time: Time::default(),
+            controls: Input::new(Bindings::default()),
+            overlay: Overlay::new(),
+            timestep: FixedTimestep::new(TICK_RATE, MAX_TICKS_PER_FRAME),
+            time_control: TimeControl::new(),
             main_camera: Camera::new(),
+            camera_effects: CameraEffects::new(),
 
             entities: Entities::new(),
-            player: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            hot_reload: HotReload::new(),
+            simulation: None,
+            player_input: PlayerInput::default(),
+            recording: None,
+            playback: None,
+            menu_selection: 0,
+            menu_rebinding: None,
+            menu_binding_slot: 0,
+            menu_slot: 0,
+            menu_slot_summary: String::new(),
+            menu_confirm_delete: false,
+            edit_camera: None,
             chosen_entity: None,
+            edit_layer: 0,
+            chosen_emitter: None,
+            placing_zone: false,
+            zone_start: None,
         }
     }
 
     pub fn setup(&mut self) {
-        self.load_level();
-        self.entities.load_entities();
+        if let Err(why) = self.load_level() {
+            eprintln!("ERROR: {why}");
+        }
+        if let Err(why) = self.entities.load_entities() {
+            eprintln!("ERROR: {why}");
+        }
+        self.load_settings();
+        self.load_save();
+        self.load_bindings();
+        self.load_overlay();
+        #[cfg(not(target_arch = "wasm32"))]
+        self.watch_files();
     }
 
     pub fn input(&mut self) {
+        self.controls.update();
+        self.overlay_input();
         match self.state {
-            WorldState::Menu => (),
+            WorldState::Menu => self.menu_input(),
             WorldState::Play => self.play_input(),
             WorldState::Edit => self.edit_input(),
-            WorldState::Debug => self.debug_input(),
+            WorldState::Debug => (),
+        }
+        // Debug doesn't tick, changes made there show once Play resumes.
+        if matches!(self.state, WorldState::Play | WorldState::Debug) {
+            self.time_input();
         }
 
-        if is_key_down(KeyCode::LeftControl) {
-            top_down_camera_controls(&mut self.main_camera);
-            if is_key_pressed(KeyCode::Space) {
+        if self.controls.down(Action::Tools) {
+            top_down_camera_controls(&mut self.main_camera, &self.controls);
+            if self.controls.pressed(Action::SwitchMode) {
                 self.state = match self.state {
-                    WorldState::Play => {
-                        self.debug_setup();
-                        WorldState::Debug
-                    }
+                    WorldState::Play => WorldState::Debug,
                     WorldState::Debug => {
                         self.edit_setup();
                         WorldState::Edit
                     }
                     WorldState::Edit => {
+                        self.edit_leave();
                         self.play_setup();
                         WorldState::Play
                     }
-                    _ => todo!(),
+                    WorldState::Menu => WorldState::Menu,
                 }
             }
         }
@@ -101,22 +202,54 @@ impl World {
         self.update_time(get_time());
         let delta = self.time.delta;
 
+        for _ in 0..self.time_control.advance(&mut self.timestep, delta) {
+            self.tick(self.timestep.tick());
+        }
+        self.time.alpha = self.timestep.alpha();
+
+        self.audio
+            .update(delta, matches!(self.state, WorldState::Menu));
+        // Ambient sounds only play while the level is played.
+        let emitters = match self.state {
+            WorldState::Play | WorldState::Menu => self.level.emitters.as_slice(),
+            WorldState::Edit | WorldState::Debug => &[],
+        };
+        self.audio
+            .update_emitters(emitters, self.main_camera.target);
+    }
+
+    // P pauses, N steps one tick, - and = halve and double the time scale.
+    fn time_input(&mut self) {
+        if self.controls.pressed(Action::TimePause) {
+            self.time_control.toggle_pause();
+        }
+        if self.controls.pressed(Action::TimeStep) {
+            self.time_control.step();
+        }
+        if self.controls.pressed(Action::TimeSlower) {
+            self.time_control.slower();
+        }
+        if self.controls.pressed(Action::TimeFaster) {
+            self.time_control.faster();
+        }
+    }
+
+    // Everything that moves the game runs here, `delta` is always one tick.
+    fn tick(&mut self, delta: f64) {
         match self.state {
-            WorldState::Menu => (),
+            WorldState::Menu | WorldState::Debug | WorldState::Edit => (),
             WorldState::Play => self.play_update(delta),
-
-            WorldState::Edit => self.edit_update(),
-            WorldState::Debug => self.debug_update(),
         }
 
-        self.main_camera.update();
+        self.camera_effects.update(delta);
     }
 
+    // Real time, `time_control` scales what the ticks get.
     fn update_time(&mut self, time: f64) {
-        let speed = 1.0;
         self.time = Time {
-            delta: (time - self.time.overall) * speed,
+            delta: time - self.time.overall,
             overall: get_time(),
+            ..self.time
         };
     }
 
@@ -126,130 +259,143 @@ impl World {
             WorldState::Debug => self.debug_draw(),
             WorldState::Play => self.play_draw(),
             WorldState::Edit => self.edit_draw(),
-            _ => todo!(),
+            WorldState::Menu => self.menu_draw(),
         }
+        self.overlay_draw();
+        self.controls.draw();
+        #[cfg(not(target_arch = "wasm32"))]
+        self.hot_reload.draw(get_time());
     }
 
     pub fn camera_set(&self) {
-        // Camera space, render game objects
-        let zoom = vec2(self.main_camera.zoom.x, -self.main_camera.zoom.y);
-        set_camera(&Camera2D {
-            target: self.main_camera.target,
-            rotation: -self.main_camera.rotation.to_degrees(),
+        set_camera(&self.camera2d());
+    }
+
+    // Camera space, render game objects
+    fn camera2d(&self) -> Camera2D {
+        let offset = self.camera_effects.offset();
+        let (target, zoom) = self.main_camera.interpolated(self.time.alpha);
+        let zoom = vec2(zoom.x, -zoom.y) * offset.zoom;
+        Camera2D {
+            target: target + offset.target,
+            rotation: -(self.main_camera.rotation + offset.rotation).to_degrees(),
             zoom,
             ..Camera2D::default()
-        });
+        }
     }
 
     #[cfg(not(target_arch = "wasm32"))]
     pub fn save_level(&self) {
-        let path = Path::new("./data/level0.txt");
+        let path = Path::new(LEVEL_PATH);
         let display = path.display();
         // Open a file in write-only mode, returns `io::Result<File>`
         match fs::read_dir("./data/") {
             Err(why) => println!("! {:?}", why.kind()),
             Ok(paths) => {
-                for path in paths {
-                    println!("> {:?}", path.unwrap().path());
+                for path in paths.flatten() {
+                    println!("> {}", path.path().display());
                 }
             }
         }
 
-        let mut file = match File::create(&path) {
-            Err(why) => panic!("couldn't create {}: {}", display, why),
+        let mut file = match File::create(path) {
+            Err(why) => panic!("couldn't create {display}: {why}"),
             Ok(file) => file,
         };
 
-        match file.write_all(format!("{}", self.static_layers).as_bytes()) {
-            Err(why) => panic!("couldn't write to {}: {}", display, why),
-            Ok(_) => println!("successfully wrote to {}", display),
+        let contents = self.level.to_string();
+        match file.write_all(contents.as_bytes()) {
+            Err(why) => panic!("couldn't write to {display}: {why}"),
+            Ok(()) => println!("successfully wrote to {display}"),
         }
     }
     #[cfg(target_arch = "wasm32")]
     pub fn save_level(&self) {}
 
     #[cfg(not(target_arch = "wasm32"))]
-    pub fn load_level(&mut self) {
-        self.static_layers = StaticLayers::new();
-        let path = Path::new("./data/level0.txt");
+    pub fn load_level(&mut self) -> Result<(), String> {
+        let path = Path::new(LEVEL_PATH);
         let display = path.display();
-        // Open a file in write-only mode, returns `io::Result<File>`
 
-        match fs::read_dir("./data/") {
-            Err(why) => println!("! {:?}", why.kind()),
-            Ok(paths) => {
-                for path in paths {
-                    println!("> {:?}", path.unwrap().path());
-                }
-            }
-        }
+        let contents =
+            fs::read_to_string(path).map_err(|why| format!("couldn't read {display}: {why}"))?;
 
-        let contents = fs::read_to_string(&path).expect("couldn't read the level");
+        // Parse everything first, so a broken file leaves the current level alone.
+        self.level = Level::parse(&contents, &display.to_string())?;
+        Ok(())
+    }
+    #[cfg(target_arch = "wasm32")]
+    pub fn load_level(&self) -> Result<(), String> {
+        Ok(())
+    }
 
+    // A missing settings file just means the defaults.
+    pub fn load_settings(&mut self) {
+        let Some(contents) = storage::load(SETTINGS_PATH) else {
+            return;
+        };
         for line in contents.lines() {
-            if line.is_empty() {
-                continue;
-            }
-            let (token, rest) = line
-                .split_once(' ')
-                .expect("Every line has at least 1 space");
-            match token {
-                "StaticLayer" => {
-                    println!("Parsed beginning of the new StaticLayer")
-                }
-                "StaticEntity" => {
-                    let mut stuff = rest
-                        .split_ascii_whitespace()
-                        .filter(|e| e != &"{" && e != &"}");
-                    let sprite = stuff.next().unwrap().trim_matches(',');
-                    let pos_x = stuff
-                        .next()
-                        .unwrap()
-                        .trim_matches(',')
-                        .split(':')
-                        .nth(1)
-                        .expect("Didn't find a second item of y:f32 (<-- this)")
-                        .parse::<f32>()
-                        .expect("Could not parse the f32");
-
-                    let pos_y = stuff
-                        .next()
-                        .unwrap()
-                        .trim_matches(',')
-                        .split(':')
-                        .nth(1)
-                        .expect("Didn't find a second item of y:f32 (<-- this)")
-                        .parse::<f32>()
-                        .expect("Could not parse the f32");
-
-                    let mut collider = [0f32; 4];
-                    for (pair, to_parse) in stuff.skip(1).enumerate() {
-                        collider[pair] = to_parse
-                            .trim_matches(',')
-                            .split(':')
-                            .nth(1)
-                            .expect("Didn't find a second item of x:f32 (<-- this)")
-                            .parse::<f32>()
-                            .expect("Could not parse the f32");
+            let (token, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let result = match token {
+                "Bus" => self.audio.mixer.parse_bus(rest),
+                "ScreenShake" => rest
+                    .trim()
+                    .parse()
+                    .map(|scale: f32| self.camera_effects.scale = scale.clamp(0.0, 1.0))
+                    .map_err(|_| format!("couldn't parse ScreenShake {rest}")),
+                "SaveSlot" => match rest.trim().parse::<usize>() {
+                    Ok(slot) if (1..=SLOTS).contains(&slot) => {
+                        self.save_slot = slot - 1;
+                        Ok(())
                     }
-                    let entity = StaticEntity::new(
-                        Vec2::new(pos_x, pos_y),
-                        sprite.to_owned(),
-                        Collider::from(collider),
-                    );
-                    println!("parsed {}", entity);
-                    self.static_layers.add_entity(0, entity);
-                }
-                _ => (),
+                    _ => Err(format!("couldn't parse SaveSlot {rest}")),
+                },
+                "TickRate" => match rest.trim().parse::<f64>() {
+                    Ok(rate) if rate >= 1.0 => {
+                        self.timestep.set_rate(rate);
+                        Ok(())
+                    }
+                    _ => Err(format!("couldn't parse TickRate {rest}")),
+                },
+                _ => Ok(()),
+            };
+            if let Err(why) = result {
+                eprintln!("ERROR: {SETTINGS_PATH}: {why}");
             }
         }
     }
-    #[cfg(target_arch = "wasm32")]
-    pub fn load_level(&self) {}
+
+    pub fn save_settings(&self) {
+        let contents = format!(
+            "{}ScreenShake {}\nTickRate {}\nSaveSlot {}\n",
+            self.audio.mixer,
+            self.camera_effects.scale,
+            self.timestep.rate(),
+            self.save_slot + 1
+        );
+        if let Err(why) = storage::save(SETTINGS_PATH, &contents) {
+            eprintln!("ERROR: {why}");
+        }
+    }
+
+    // A missing bindings file just means the defaults.
+    fn load_bindings(&mut self) {
+        if let Some(contents) = storage::load(BINDINGS_PATH) {
+            self.controls.bindings = Bindings::parse(&contents);
+        }
+    }
+
+    pub fn save_bindings(&self) {
+        if let Err(why) = storage::save(BINDINGS_PATH, &self.controls.bindings.to_string()) {
+            eprintln!("ERROR: {why}");
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
 struct Time {
     delta: f64,
     overall: f64,
+    // How far drawing is between the last two ticks.
+    alpha: f32,
 }
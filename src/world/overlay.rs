@@ -0,0 +1,77 @@
+use macroquad::prelude::*;
+
+use crate::input::Action;
+use crate::overlay::{Fps, Overlay, Panels, OVERLAY_PATH};
+use crate::simulation::Simulation;
+use crate::storage;
+use crate::world::{World, WorldState};
+
+impl World {
+    // Works in every state, see `Overlay`.
+    pub(super) fn overlay_input(&mut self) {
+        let mut changed = self.controls.pressed(Action::DebugOverlay);
+        if changed {
+            self.overlay.visible = !self.overlay.visible;
+        }
+        if self.overlay.visible {
+            if self.controls.pressed(Action::DebugPanelNext) {
+                let names = self.overlay_panels().names();
+                self.overlay.select_next(&names);
+            }
+            if self.controls.pressed(Action::DebugPanelToggle) {
+                self.overlay.toggle_selected();
+                changed = true;
+            }
+        }
+        if changed {
+            self.save_overlay();
+        }
+    }
+
+    // The run being played, the editor's level can differ from it.
+    const fn overlay_simulation(&self) -> Option<&Simulation> {
+        match self.state {
+            WorldState::Play | WorldState::Menu | WorldState::Debug => self.simulation.as_ref(),
+            WorldState::Edit => None,
+        }
+    }
+
+    // Every subsystem registers its own panels.
+    fn overlay_panels(&self) -> Panels<'_> {
+        let mut panels = Panels::new();
+        panels.register("fps", Fps);
+        self.time_control.register_panels(&mut panels);
+        self.main_camera.register_panels(&mut panels);
+        self.controls.register_panels(&mut panels);
+        if let Some(simulation) = self.overlay_simulation() {
+            simulation.register_panels(&mut panels, self.time.alpha);
+        }
+        self.audio.register_panels(&mut panels);
+        panels
+    }
+
+    pub(super) fn overlay_draw(&self) {
+        let bindings = &self.controls.bindings;
+        let hint = format!(
+            "{} toggle, {} next",
+            bindings.label(Action::DebugPanelToggle),
+            bindings.label(Action::DebugPanelNext)
+        );
+        self.overlay
+            .draw(&self.overlay_panels(), &self.camera2d(), &hint);
+        set_default_camera();
+    }
+
+    // A missing overlay file just means the defaults.
+    pub(super) fn load_overlay(&mut self) {
+        if let Some(contents) = storage::load(OVERLAY_PATH) {
+            self.overlay = Overlay::parse(&contents);
+        }
+    }
+
+    fn save_overlay(&self) {
+        if let Err(why) = storage::save(OVERLAY_PATH, &self.overlay.to_string()) {
+            eprintln!("ERROR: {why}");
+        }
+    }
+}
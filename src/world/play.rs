@@ -1,105 +1,154 @@
 use macroquad::prelude::*;
 
-use crate::collider::Collider;
-use crate::player::Player;
-use crate::static_layers::StaticEntity;
+use crate::camera::{Follow, Framing};
+use crate::input::Action;
+use crate::player::{PlayerEvent, PlayerInput};
+use crate::simulation::{Simulation, SimulationEvent};
+use crate::trigger::TriggerAction;
 use crate::world::World;
 
 impl World {
     pub(super) fn play_setup(&mut self) {
-        let spawn = "char1-spawn";
-        if let Some(player_spawn) = self.static_layers.find(spawn) {
-            let StaticEntity {
-                pos,
-                collider,
-                sprite,
-            } = player_spawn;
-            let collider = collider
-                .clone()
-                .expect("player_spawn should have a Collider");
-            let sprites: Vec<String> = vec![
-                "char1-idle".to_owned(),
-                "char1-jump".to_owned(),
-                "char1-fall".to_owned(),
-            ];
-            let new_collider = Collider::new(collider.pos, 0.0, 0.0);
-            self.player = Some(Player::new(*pos, collider, &sprites));
-            let new_spawn = StaticEntity::new(*pos, sprite.clone(), new_collider);
-            self.static_layers.replace(new_spawn);
+        let clips = self.sprites.get_clips("char1-");
+        match Simulation::new(&self.level, &clips, &self.save.abilities) {
+            Ok(simulation) => {
+                self.main_camera
+                    .set_follow(Some(Follow::new(simulation.spawn_pos)));
+                self.simulation = Some(simulation);
+            }
+            Err(why) => {
+                eprintln!("ERROR: {why}");
+                self.simulation = None;
+            }
         }
+        self.hide_collected();
+        self.audio.play_music(self.level.music.as_deref());
     }
 
     pub(super) fn play_input(&mut self) {
-        let _w = is_key_down(KeyCode::W) || is_key_down(KeyCode::Comma);
-        let _s = is_key_down(KeyCode::S) || is_key_down(KeyCode::O);
-        let a = is_key_down(KeyCode::A);
-        let d = is_key_down(KeyCode::D) || is_key_down(KeyCode::E);
-
-        if let Some(player) = &mut self.player {
-            if is_key_down(KeyCode::Space) {
-                player.jump();
-            } else {
-                player.jump_stop();
-            }
-            if a {
-                player.left();
-            } else if d {
-                player.right();
-            } else {
-                player.stop();
-            }
+        if self.controls.pressed(Action::Pause) {
+            self.menu_open();
+            return;
         }
+        self.replay_input();
+        self.player_input = PlayerInput {
+            left: self.controls.down(Action::MoveLeft),
+            right: self.controls.down(Action::MoveRight),
+            jump: self.controls.down(Action::Jump),
+            dash: self.controls.down(Action::Dash),
+        };
     }
 
     pub(super) fn play_update(&mut self, delta: f64) {
-        if let Some(player) = &mut self.player {
-            player.update(&self.static_layers, delta);
-            self.main_camera.target += (player.pos - self.main_camera.target) * 0.2;
+        let input = self.tick_input();
+        let Some(simulation) = &mut self.simulation else {
+            return;
+        };
+        let events = simulation.tick(input, delta);
+        let player = &simulation.player;
+        let level = Framing {
+            bounds: self.level.bounds,
+            ..Framing::default()
+        };
+        let framing = self
+            .level
+            .zone_at(player.pos)
+            .and_then(|index| self.level.camera_zones.get(index))
+            .map_or(level, |zone| zone.framing(level));
+        self.main_camera.update_follow(
+            player.pos,
+            player.facing_right(),
+            player.on_ground,
+            framing,
+            delta,
+        );
+        let player_pos = player.pos;
+        let ticks = simulation.ticks;
+        for event in events {
+            match event {
+                SimulationEvent::Player(PlayerEvent::Jumped) => {
+                    self.audio.play_at("jump", player_pos, &self.main_camera);
+                }
+                SimulationEvent::Player(PlayerEvent::Landed(height)) => {
+                    self.audio.play_at("land", player_pos, &self.main_camera);
+                    if height > BIG_FALL {
+                        let trauma = (height - BIG_FALL) / BIG_FALL;
+                        self.camera_effects.add_trauma(trauma.min(0.6));
+                        self.camera_effects.zoom_punch(0.05);
+                    }
+                }
+                // Clip frames name the cue they play, footsteps for example.
+                SimulationEvent::Player(PlayerEvent::Animation(cue)) => {
+                    self.audio.play_at(&cue, player_pos, &self.main_camera);
+                }
+                SimulationEvent::Died(pos) => {
+                    self.audio.play_at("death", pos, &self.main_camera);
+                    self.camera_effects.add_trauma(0.8);
+                    self.camera_effects.zoom_punch(0.15);
+                    self.camera_effects.rotation_kick(0.08);
+                }
+                SimulationEvent::Checkpoint(pos) => {
+                    self.audio.play_at("checkpoint", pos, &self.main_camera);
+                    self.save_checkpoint(pos);
+                }
+                SimulationEvent::Trigger(TriggerAction::GrantAbility(ability), pos) => {
+                    self.audio.play_at("pickup", pos, &self.main_camera);
+                    self.unlock_ability(ability);
+                }
+                SimulationEvent::Trigger(TriggerAction::Music(id), _) => {
+                    self.audio.play_music(Some(&id));
+                }
+                SimulationEvent::Trigger(TriggerAction::Collect(id), pos) => {
+                    self.audio.play_at("collect", pos, &self.main_camera);
+                    self.collect(&id);
+                }
+                SimulationEvent::Trigger(TriggerAction::Finish(next), pos) => {
+                    self.audio.play_at("finish", pos, &self.main_camera);
+                    self.camera_effects.zoom_punch(0.1);
+                    self.finish_level(&next, ticks);
+                }
+            }
         }
     }
 
+    // Debug drawing is left to the overlay, see `overlay_draw`.
     pub(super) fn play_draw(&self) {
-        let Rect { x, y, w, h } = self.main_camera.viewport_rect();
-        draw_rectangle_lines(x, y, w, h, w / 100.0, color_u8!(50, 120, 100, 100));
-        let (width, height) = (screen_width(), screen_height());
-        let (center_x, center_y) = (self.main_camera.target.x, self.main_camera.target.y);
-        let top_left_x = center_x - width;
-        let top_left_y = center_y - height;
-        draw_rectangle_lines(
-            top_left_x,
-            top_left_y,
-            width * 2.0,
-            height * 2.0,
-            50.0,
-            color_u8!(50, 120, 100, 100),
-        );
-
-        draw_rectangle(-5.0, -5.0, 10.0, 10.0, color_u8!(180, 180, 180, 255));
-
-        self.static_layers.draw(&self.sprites);
-
-        if let Some(player) = &self.player {
-            player.draw(&self.sprites);
-            player.collider.draw(player.pos);
+        if let Some(simulation) = &self.simulation {
+            simulation
+                .static_layers
+                .draw(&self.sprites, &self.camera2d());
+            for trigger in &simulation.triggers {
+                trigger.draw(&self.sprites);
+            }
+            for checkpoint in &simulation.checkpoints {
+                checkpoint.draw(&self.sprites);
+            }
+            simulation.player.draw(&self.sprites, self.time.alpha);
         }
 
         set_default_camera();
 
-        if let Some(player) = &self.player {
+        if let Some(status) = self.replay_status() {
+            let size = measure_text(&status, None, 18, 1.0);
             draw_text(
-                &format!("{}", player),
-                10.0,
+                &status,
+                (screen_width() - size.width) / 2.0,
+                20.0,
+                18.0,
+                color_u8!(255, 90, 90, 255),
+            );
+        }
+        if self.time_control.is_altered() {
+            draw_text(
+                &self.time_control.to_string(),
                 10.0,
+                screen_height() - 20.0,
                 18.0,
                 color_u8!(255, 255, 255, 255),
             );
         }
-        draw_text(
-            &format!("FPS: {}", get_fps()),
-            10.0,
-            30.0,
-            18.0,
-            color_u8!(255, 255, 255, 255),
-        );
     }
 }
+
+// Landing after falling further than this shakes the camera.
+const BIG_FALL: f32 = 1000.0;
@@ -0,0 +1,78 @@
+use std::fs;
+use std::path::Path;
+
+use macroquad::prelude::*;
+
+use crate::entity::ENTITIES_PATH;
+use crate::manifest::MANIFEST_PATH;
+use crate::world::{World, WorldState, LEVEL_PATH};
+
+const ASSETS_FOLDER: &str = "assets";
+
+impl World {
+    pub(super) fn watch_files(&mut self) {
+        for path in self
+            .sprites
+            .paths()
+            .chain(self.sprites.clip_paths())
+            .chain(self.audio.paths())
+            .chain(self.audio.cue_paths())
+        {
+            self.hot_reload.watch(Path::new(ASSETS_FOLDER).join(path));
+        }
+        self.hot_reload
+            .watch(Path::new(ASSETS_FOLDER).join(MANIFEST_PATH));
+        self.hot_reload.watch(LEVEL_PATH);
+        self.hot_reload.watch(ENTITIES_PATH);
+    }
+
+    // Sounds load through macroquad's futures, which only run on the main
+    // thread and aren't Send.
+    #[allow(clippy::future_not_send)]
+    pub async fn hot_reload(&mut self) {
+        let time = get_time();
+        for path in self.hot_reload.poll(time) {
+            let result = self.reload_file(&path).await;
+            self.hot_reload.report(time, result);
+        }
+    }
+
+    #[allow(clippy::future_not_send)]
+    async fn reload_file(&mut self, path: &Path) -> Result<String, String> {
+        let display = path.display();
+        if path == Path::new(LEVEL_PATH) {
+            self.load_level()?;
+            // Play changes the level while running, so start it over.
+            if matches!(self.state, WorldState::Play) {
+                self.play_setup();
+            }
+        } else if path == Path::new(ENTITIES_PATH) {
+            self.entities.load_entities()?;
+            self.chosen_entity = None;
+        } else if path == Path::new(ASSETS_FOLDER).join(MANIFEST_PATH) {
+            return Err(format!("{display} changed, restart to apply it"));
+        } else {
+            let bytes = fs::read(path).map_err(|why| format!("couldn't read {display}: {why}"))?;
+            let asset = path
+                .strip_prefix(ASSETS_FOLDER)
+                .ok()
+                .and_then(Path::to_str)
+                .ok_or_else(|| format!("{display} is not an asset"))?;
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some("png") => self.sprites.reload(asset, &bytes)?,
+                Some("ogg") => self.audio.reload(asset, &bytes).await?,
+                Some("txt") => {
+                    let contents = String::from_utf8(bytes)
+                        .map_err(|why| format!("{display} is not utf-8: {why}"))?;
+                    if self.audio.cue_paths().any(|cues| cues == asset) {
+                        self.audio.reload_cues(asset, contents)?;
+                    } else {
+                        self.sprites.reload_clips(asset, contents)?;
+                    }
+                }
+                _ => return Err(format!("Don't know how to reload {display}")),
+            }
+        }
+        Ok(format!("Reloaded {display}"))
+    }
+}
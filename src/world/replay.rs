@@ -0,0 +1,112 @@
+use macroquad::miniquad::date;
+use macroquad::prelude::*;
+
+use crate::ability::Abilities;
+use crate::input::Action;
+use crate::player::PlayerInput;
+use crate::replay::{level_hash, Playback, Replay, REPLAY_PATH};
+use crate::storage;
+use crate::world::{World, LEVEL_PATH};
+
+impl World {
+    // F9 restarts the level and records, pressing it again saves the replay.
+    // F10 plays the saved replay back.
+    pub(super) fn replay_input(&mut self) {
+        if self.controls.pressed(Action::ReplayRecord) {
+            if let Some(replay) = self.recording.take() {
+                match storage::save(REPLAY_PATH, &replay.to_string()) {
+                    Ok(()) => println!("Saved {} ticks to {}", replay.inputs.len(), REPLAY_PATH),
+                    Err(why) => eprintln!("ERROR: {why}"),
+                }
+            } else {
+                self.replay_stop();
+                let seed = date::now().to_bits();
+                self.restart_seeded(seed);
+                self.recording = Some(Replay::new(
+                    LEVEL_PATH,
+                    level_hash(&self.level.to_string()),
+                    seed,
+                    self.timestep.rate(),
+                    &self.save.abilities,
+                ));
+            }
+        }
+        if self.controls.pressed(Action::ReplayPlay) {
+            self.recording = None;
+            if let Err(why) = self.replay_start() {
+                eprintln!("ERROR: {why}");
+            }
+        }
+    }
+
+    fn replay_start(&mut self) -> Result<(), String> {
+        let contents =
+            storage::load(REPLAY_PATH).ok_or_else(|| format!("couldn't read {REPLAY_PATH}"))?;
+        let replay = Replay::parse(&contents)?;
+        if replay.level != LEVEL_PATH {
+            return Err(format!(
+                "{} was recorded on {}, not {}",
+                REPLAY_PATH, replay.level, LEVEL_PATH
+            ));
+        }
+        // Unsaved edits in the editor change the level the run plays on.
+        if replay.level_hash != level_hash(&self.level.to_string()) {
+            return Err(format!(
+                "{REPLAY_PATH} was recorded on a different version of {LEVEL_PATH}"
+            ));
+        }
+        self.replay_stop();
+        let previous_tick_rate = self.timestep.rate();
+        self.timestep.set_rate(replay.tick_rate);
+        self.restart_seeded(replay.seed);
+        if let Some(simulation) = &mut self.simulation {
+            // The run starts with what was unlocked when it was recorded.
+            simulation.player.abilities = Abilities::new(&replay.abilities);
+        }
+        println!("Playing {} ticks from {}", replay.inputs.len(), REPLAY_PATH);
+        self.playback = Some(Playback::new(replay, previous_tick_rate));
+        Ok(())
+    }
+
+    pub(super) fn replay_stop(&mut self) {
+        if let Some(playback) = self.playback.take() {
+            self.timestep.set_rate(playback.previous_tick_rate);
+        }
+    }
+
+    // Starts the level the same way every time. It runs on the level in the
+    // editor, `replay_start` checks that it is the one that was recorded.
+    fn restart_seeded(&mut self, seed: u64) {
+        rand::srand(seed);
+        self.play_setup();
+    }
+
+    // Input for the next tick, from the replay while one plays.
+    pub(super) fn tick_input(&mut self) -> PlayerInput {
+        if let Some(playback) = &mut self.playback {
+            if let Some(input) = playback.next_input() {
+                return input;
+            }
+            println!("Replay finished");
+            self.replay_stop();
+        }
+        let input = self.player_input;
+        if let Some(replay) = &mut self.recording {
+            replay.inputs.push(input);
+        }
+        input
+    }
+
+    pub(super) fn replay_status(&self) -> Option<String> {
+        if let Some(replay) = &self.recording {
+            Some(format!(
+                "recording, {} ticks (F9 saves)",
+                replay.inputs.len()
+            ))
+        } else {
+            self.playback
+                .as_ref()
+                .map(|playback| format!("replaying {} ticks", playback.replay.inputs.len()))
+        }
+    }
+}
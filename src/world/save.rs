@@ -0,0 +1,147 @@
+use macroquad::math::Vec2;
+
+use crate::ability::Ability;
+use crate::save::{slot_path, SaveGame, SavedCheckpoint, LEGACY_SAVE_PATH};
+use crate::storage;
+use crate::trigger::TriggerAction;
+use crate::world::{World, LEVEL_NAME};
+
+impl World {
+    // The slot picked in the settings, an empty one is a fresh start.
+    pub(super) fn load_save(&mut self) {
+        match read_slot(self.save_slot) {
+            Ok(save) => {
+                self.save = save.unwrap_or_default();
+                self.save_error = None;
+            }
+            Err(why) => {
+                eprintln!("ERROR: {}: {}", slot_path(self.save_slot), why);
+                self.save = SaveGame::default();
+                self.save_error = Some(why);
+            }
+        }
+        // Old saves don't list any levels.
+        self.save.unlock(LEVEL_NAME);
+    }
+
+    fn save_progress(&self) {
+        let path = slot_path(self.save_slot);
+        if let Some(why) = &self.save_error {
+            eprintln!("ERROR: Not writing over {path}, it could not be read: {why}");
+            return;
+        }
+        match storage::save(&path, &self.save.to_string()) {
+            Err(why) => eprintln!("ERROR: {why}"),
+            Ok(()) => println!("successfully wrote to {path}"),
+        }
+    }
+
+    // Makes the slot the current one and restarts the level, from the last
+    // checkpoint when continuing.
+    pub(super) fn load_slot(&mut self, slot: usize, resume: bool) {
+        self.save_slot = slot;
+        self.save_settings();
+        self.load_save();
+        self.replay_stop();
+        self.recording = None;
+        self.play_setup();
+        let checkpoint = self
+            .save
+            .checkpoint
+            .as_ref()
+            .filter(|checkpoint| resume && checkpoint.level == LEVEL_NAME);
+        if let (Some(checkpoint), Some(simulation)) = (checkpoint, &mut self.simulation) {
+            simulation.respawn_at(checkpoint.pos);
+        }
+    }
+
+    pub(super) fn delete_slot(&mut self, slot: usize) {
+        let mut paths = vec![slot_path(slot)];
+        if slot == 0 {
+            paths.push(LEGACY_SAVE_PATH.to_owned());
+        }
+        for path in paths {
+            if let Err(why) = storage::remove(&path) {
+                eprintln!("ERROR: {why}");
+            }
+        }
+        if slot == self.save_slot {
+            self.save = SaveGame::new(LEVEL_NAME);
+            self.save_error = None;
+            self.play_setup();
+        }
+    }
+
+    // Collected pickups stay gone on this slot.
+    pub(super) fn hide_collected(&mut self) {
+        let Some(simulation) = &mut self.simulation else {
+            return;
+        };
+        for trigger in &mut simulation.triggers {
+            if let TriggerAction::Collect(id) = &trigger.action {
+                trigger.fired = self.save.is_collected(LEVEL_NAME, id);
+            }
+        }
+    }
+
+    // Nothing that happens in a replay counts as progress.
+    const fn records_progress(&self) -> bool {
+        self.playback.is_none()
+    }
+
+    pub(super) fn unlock_ability(&mut self, ability: Ability) {
+        if self.records_progress() && !self.save.abilities.contains(&ability) {
+            self.save.abilities.push(ability);
+            self.save_progress();
+        }
+        if let Some(simulation) = &mut self.simulation {
+            simulation.player.abilities.grant(ability);
+        }
+    }
+
+    pub(super) fn collect(&mut self, id: &str) {
+        if self.records_progress() && self.save.collect(LEVEL_NAME, id) {
+            self.save_progress();
+        }
+    }
+
+    // Continuing the slot starts here.
+    pub(super) fn save_checkpoint(&mut self, pos: Vec2) {
+        if self.records_progress() {
+            self.save.checkpoint = Some(SavedCheckpoint {
+                level: LEVEL_NAME.to_owned(),
+                pos,
+            });
+            self.save_progress();
+        }
+    }
+
+    // Completes the level after `ticks` and unlocks `next`.
+    pub(super) fn finish_level(&mut self, next: &str, ticks: usize) {
+        let ticks = u32::try_from(ticks).unwrap_or(u32::MAX);
+        let time = f64::from(ticks) / self.timestep.rate();
+        println!("Finished {LEVEL_NAME} in {time:.2}s");
+        if !self.records_progress() {
+            return;
+        }
+        if self.save.complete(LEVEL_NAME, time) {
+            println!("New best time");
+        }
+        self.save.unlock(next);
+        self.save.checkpoint = None;
+        self.save_progress();
+    }
+}
+
+// None when the slot is empty, an error when it can't be parsed. The first
+// slot falls back to the save from before there were slots.
+pub(super) fn read_slot(slot: usize) -> Result<Option<SaveGame>, String> {
+    let contents = storage::load(&slot_path(slot)).or_else(|| {
+        (slot == 0)
+            .then(|| storage::load(LEGACY_SAVE_PATH))
+            .flatten()
+    });
+    contents
+        .map(|contents| SaveGame::parse(&contents))
+        .transpose()
+}
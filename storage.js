@@ -0,0 +1,36 @@
+// localStorage for src/storage.rs, keys are the file names the game uses natively.
+
+function storage_key(ptr, len) {
+    return "kaika/" + UTF8ToString(ptr, len);
+}
+
+function storage_set(key, key_len, value, value_len) {
+    localStorage.setItem(storage_key(key, key_len), UTF8ToString(value, value_len));
+}
+
+function storage_len(key, key_len) {
+    var value = localStorage.getItem(storage_key(key, key_len));
+    if (value === null) {
+        return -1;
+    }
+    return new TextEncoder().encode(value).length;
+}
+
+function storage_get(key, key_len, buffer, buffer_len) {
+    var value = localStorage.getItem(storage_key(key, key_len)) || "";
+    var bytes = new TextEncoder().encode(value).subarray(0, buffer_len);
+    new Uint8Array(wasm_memory.buffer, buffer, buffer_len).set(bytes);
+}
+
+function storage_remove(key, key_len) {
+    localStorage.removeItem(storage_key(key, key_len));
+}
+
+function storage_register_plugin(importObject) {
+    importObject.env.storage_set = storage_set;
+    importObject.env.storage_len = storage_len;
+    importObject.env.storage_get = storage_get;
+    importObject.env.storage_remove = storage_remove;
+}
+
+miniquad_add_plugin({ register_plugin: storage_register_plugin, version: "0.2.0", name: "kaika_storage" });